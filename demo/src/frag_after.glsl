@@ -1,6 +1,10 @@
 
 uniform mat4 camera;
+uniform int ortho;
+uniform vec2 view_center;
+uniform float view_zoom;
 uniform int show_grid;
+uniform int show_tree;
 
 uniform vec2 offset;
 uniform vec2 size;
@@ -11,6 +15,11 @@ varying vec2 uv_screen;
 void main() {
     vec3 o = mul_pos(camera, vec3(0.));
     vec3 d = normalize(mul_dir(camera, vec3(uv_screen.x, uv_screen.y, 1.)));
+    if (ortho == 1) {
+        // Rays are parallel, min side of the screen covers `1 / view_zoom` of the plane
+        o = vec3(view_center + uv_screen * 0.5 / view_zoom, 1.);
+        d = vec3(0., 0., -1.);
+    }
     Ray r = Ray(o, d);
 
     gl_FragColor = vec4(0.8, 0.8, 0.8, 1.);        
@@ -20,7 +29,8 @@ void main() {
         vec3 pos = r.o + r.d * t;
         float prop = max(size.x, size.y);
         vec2 coord = (pos.xy + vec2(0.5) * size / prop) * prop + offset;
-        if (is_inside_polygon(coord)) {
+        bool inside = is_inside_polygon(coord);
+        if (inside) {
             vec3 clr = add_normal_to_color(color(0.4, 0.4, 0.4), vec3(0., 0., 1.), r.d);
 
             if (show_grid == 1) {
@@ -29,5 +39,13 @@ void main() {
 
             gl_FragColor = vec4(sqrt(clr), 1.);
         }
+
+        if (show_tree == 1) {
+            vec2 leaf = leaf_polygon(coord);
+            if (leaf.x >= 0.) {
+                vec3 clr = mix(leaf_color(leaf.x), depth_color(leaf.y / max(tree_max_depth, 1.)), 0.5);
+                gl_FragColor.rgb = mix(gl_FragColor.rgb, clr, inside ? 0.35 : 0.6);
+            }
+        }
     }
 }
\ No newline at end of file
@@ -27,6 +27,16 @@ vec3 color(float r, float g, float b) {
     return vec3(r*r, g*g, b*b);
 }
 
+// Random color for every leaf of the tree
+vec3 leaf_color(float leaf) {
+    return fract(sin(vec3(leaf * 12.9898, leaf * 78.233, leaf * 37.719)) * 43758.5453) * 0.6 + 0.4;
+}
+
+// From blue for leaves near the root to red for the deepest leaves, as in the tree visualizer
+vec3 depth_color(float t) {
+    return mix(vec3(0.24, 0.47, 0.94), vec3(0.94, 0.31, 0.24), t);
+}
+
 vec3 add_normal_to_color(vec3 color, vec3 normal, vec3 direction) {
     const float not_dark_count = 0.4;
     color *= (abs(dot(normalize(direction), normalize(normal))) + not_dark_count) / (1. + not_dark_count);
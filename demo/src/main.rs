@@ -0,0 +1,1156 @@
+use std::{
+	f32::consts::PI,
+	fs,
+	path::{Path, PathBuf},
+};
+
+use fast_point_in_polygon_for_glsl::*;
+use geo::{Coordinate, Line, LineString, MultiPolygon, Point};
+use macroquad::{
+	hash,
+	prelude::*,
+	ui::{root_ui, widgets::Window},
+	Error,
+};
+
+struct RotateAroundCam {
+	alpha: f32,
+	beta: f32,
+	r: f32,
+	previous_mouse: Vec2,
+}
+
+impl RotateAroundCam {
+	const BETA_MAX: f32 = PI - 0.01;
+	const BETA_MIN: f32 = 0.01;
+	const MOUSE_SENSITIVITY: f32 = 1.2;
+	const SCALE_FACTOR: f32 = 1.1;
+	const VIEW_ANGLE: f32 = 80. / 180. * PI;
+
+	fn new() -> Self {
+		Self {
+			alpha: -PI / 2.,
+			beta: PI / 2.,
+			r: 0.8,
+			previous_mouse: Vec2::default(),
+		}
+	}
+
+	fn process_mouse_and_keys(&mut self, allow_rotation: bool) -> bool {
+		let mut is_something_changed = false;
+
+		let mouse_pos: Vec2 = mouse_position_local();
+
+		if allow_rotation && is_mouse_button_down(MouseButton::Left) {
+			let dalpha = (mouse_pos.x - self.previous_mouse.x) * Self::MOUSE_SENSITIVITY;
+			let dbeta = (mouse_pos.y - self.previous_mouse.y) * Self::MOUSE_SENSITIVITY;
+
+			self.alpha += dalpha;
+			self.beta = clamp(self.beta + dbeta, Self::BETA_MIN, Self::BETA_MAX);
+
+			is_something_changed = true;
+		}
+
+		let wheel_value = mouse_wheel().1;
+		if wheel_value > 0. {
+			self.r *= 1.0 / Self::SCALE_FACTOR;
+			is_something_changed = true;
+		} else if wheel_value < 0. {
+			self.r *= Self::SCALE_FACTOR;
+			is_something_changed = true;
+		}
+
+		self.previous_mouse = mouse_pos;
+
+		return is_something_changed;
+	}
+
+	fn get_matrix(&self) -> Mat4 {
+		let pos = Vec3::new(
+			-self.beta.sin() * self.alpha.cos(),
+			self.beta.cos(),
+			-self.beta.sin() * self.alpha.sin(),
+		) * self.r;
+		let look_at = Vec3::new(0., 0., 0.);
+
+		let h = (Self::VIEW_ANGLE / 2.).tan();
+
+		let k = (look_at - pos).normalize();
+		let i = k.cross(Vec3::new(0., 1., 0.)).normalize() * h;
+		let j = k.cross(i).normalize() * h;
+
+		Mat4::from_cols(
+			Vec4::new(i.x, i.y, i.z, 0.),
+			Vec4::new(j.x, j.y, j.z, 0.),
+			Vec4::new(k.x, k.y, k.z, 0.),
+			Vec4::new(pos.x, pos.y, pos.z, 1.),
+		)
+	}
+}
+
+// Pan and zoom of the z=0 plane without perspective, pixels map directly to polygon coordinates
+#[derive(Clone, Copy)]
+struct OrthoCam {
+	center: Vec2,
+	// Min side of the screen covers `1 / zoom` of the plane
+	zoom: f32,
+	previous_mouse: Vec2,
+}
+
+impl OrthoCam {
+	const SCALE_FACTOR: f32 = 1.1;
+
+	fn new() -> Self {
+		Self {
+			center: Vec2::new(0., 0.),
+			zoom: 1.,
+			previous_mouse: Vec2::default(),
+		}
+	}
+
+	fn process_mouse_and_keys(&mut self, allow_pan: bool) {
+		let mouse_pos = Vec2::from(mouse_position());
+
+		if allow_pan && is_mouse_button_down(MouseButton::Left) {
+			let delta = self.to_plane(mouse_pos) - self.to_plane(self.previous_mouse);
+			self.center -= delta;
+		}
+
+		// Point under the cursor stays on its place
+		let wheel_value = mouse_wheel().1;
+		if wheel_value != 0. {
+			let before = self.to_plane(mouse_pos);
+			if wheel_value > 0. {
+				self.zoom *= Self::SCALE_FACTOR;
+			} else {
+				self.zoom /= Self::SCALE_FACTOR;
+			}
+			self.center += before - self.to_plane(mouse_pos);
+		}
+
+		self.previous_mouse = mouse_pos;
+	}
+
+	fn to_plane(&self, pos: Vec2) -> Vec2 {
+		let min = screen_width().min(screen_height());
+		let pos = Vec2::new(pos.x - screen_width() / 2., screen_height() / 2. - pos.y);
+		self.center + pos / (min * self.zoom)
+	}
+
+	fn to_screen(&self, plane: Vec2) -> Vec2 {
+		let min = screen_width().min(screen_height());
+		let pos = (plane - self.center) * (min * self.zoom);
+		Vec2::new(pos.x + screen_width() / 2., screen_height() / 2. - pos.y)
+	}
+}
+
+#[derive(Clone, Copy)]
+enum Projection {
+	Perspective(Mat4),
+	Orthographic(OrthoCam),
+}
+
+// Mapping between screen and polygon coordinates, the same as in `frag_after.glsl`
+struct PlaneMapping {
+	projection: Projection,
+	offset: (f32, f32),
+	size: (f32, f32),
+}
+
+impl PlaneMapping {
+	fn screen_to_polygon(&self, pos: (f32, f32)) -> Option<(f64, f64)> {
+		let plane = match self.projection {
+			Projection::Perspective(camera) => {
+				let min = screen_width().min(screen_height());
+				let uv = Vec2::new(
+					(pos.0 - screen_width() / 2.) / min * 2.,
+					-(pos.1 - screen_height() / 2.) / min * 2.,
+				);
+				let o = camera * Vec4::new(0., 0., 0., 1.);
+				let d = camera * Vec4::new(uv.x, uv.y, 1., 0.);
+				let t = -o.z / d.z;
+				if !(t > 0.) {
+					return None;
+				}
+				Vec2::new(o.x + d.x * t, o.y + d.y * t)
+			},
+			Projection::Orthographic(cam) => cam.to_plane(Vec2::from(pos)),
+		};
+		let prop = self.size.0.max(self.size.1);
+		Some((
+			((plane.x + 0.5 * self.size.0 / prop) * prop + self.offset.0) as f64,
+			((plane.y + 0.5 * self.size.1 / prop) * prop + self.offset.1) as f64,
+		))
+	}
+
+	fn polygon_to_screen(&self, coord: (f64, f64)) -> Option<Vec2> {
+		let prop = self.size.0.max(self.size.1);
+		let x = (coord.0 as f32 - self.offset.0) / prop - 0.5 * self.size.0 / prop;
+		let y = (coord.1 as f32 - self.offset.1) / prop - 0.5 * self.size.1 / prop;
+		match self.projection {
+			Projection::Perspective(camera) => {
+				let local = camera.inverse() * Vec4::new(x, y, 0., 1.);
+				if !(local.z > 0.) {
+					return None;
+				}
+				let min = screen_width().min(screen_height());
+				Some(Vec2::new(
+					local.x / local.z * min / 2. + screen_width() / 2.,
+					-local.y / local.z * min / 2. + screen_height() / 2.,
+				))
+			},
+			Projection::Orthographic(cam) => Some(cam.to_screen(Vec2::new(x, y))),
+		}
+	}
+}
+
+// Lines of the points text with the line of every vertex, edits change only lines of edited vertices, so comments and blank lines are kept
+struct PointsText {
+	lines: Vec<String>,
+	// In the order of `parse_rings`
+	vertices: Vec<Vec<usize>>,
+	trailing_newline: bool,
+}
+
+impl PointsText {
+	fn new(text: &str) -> Self {
+		let mut result = Self {
+			lines: text.lines().map(str::to_owned).collect(),
+			vertices: Vec::new(),
+			trailing_newline: text.ends_with('\n'),
+		};
+		result.find_vertices();
+		result
+	}
+
+	// Text before the comment, as `parse_rings` reads it
+	fn content(line: &str) -> &str {
+		let comment = [line.find('#'), line.find("//")]
+			.iter()
+			.filter_map(|x| *x)
+			.min();
+		&line[..comment.unwrap_or_else(|| line.len())]
+	}
+
+	fn find_vertices(&mut self) {
+		self.vertices.clear();
+		let mut ring = Vec::new();
+		for (index, line) in self.lines.iter().enumerate() {
+			let content = Self::content(line);
+			if content
+				.split(|c: char| c.is_whitespace() || c == ',')
+				.any(|token| !token.is_empty())
+			{
+				ring.push(index);
+			} else if content.len() == line.len() && !ring.is_empty() {
+				// Line with only a comment doesn't break the ring
+				self.vertices.push(std::mem::take(&mut ring));
+			}
+		}
+		if !ring.is_empty() {
+			self.vertices.push(ring);
+		}
+	}
+
+	fn set(&mut self, (r, i): (usize, usize), coord: Coordinate<f64>) {
+		let line = &mut self.lines[self.vertices[r][i]];
+		let content = Self::content(line);
+		let indent = content.len() - content.trim_start().len();
+		let end = content.trim_end().len();
+		let edited = format!("{}{} {}{}", &line[..indent], coord.x, coord.y, &line[end..]);
+		*line = edited;
+	}
+
+	// New vertex goes after the vertex `(r, i)`
+	fn insert(&mut self, (r, i): (usize, usize), coord: Coordinate<f64>) {
+		self.lines
+			.insert(self.vertices[r][i] + 1, format!("{} {}", coord.x, coord.y));
+		self.find_vertices();
+	}
+
+	fn push_ring(&mut self, ring: &[Coordinate<f64>]) {
+		if self
+			.lines
+			.last()
+			.map_or(false, |line| !line.trim().is_empty())
+		{
+			self.lines.push(String::new());
+		}
+		self.lines
+			.extend(ring.iter().map(|coord| format!("{} {}", coord.x, coord.y)));
+		self.find_vertices();
+	}
+
+	// Comment of the removed vertex stays on its line
+	fn remove_line(&mut self, index: usize) {
+		let line = &self.lines[index];
+		let content = Self::content(line);
+		if content.len() == line.len() {
+			self.lines.remove(index);
+		} else {
+			let indent = content.len() - content.trim_start().len();
+			let comment = format!("{}{}", &line[..indent], &line[content.len()..]);
+			self.lines[index] = comment;
+		}
+	}
+
+	fn remove(&mut self, (r, i): (usize, usize)) {
+		self.remove_line(self.vertices[r][i]);
+		self.find_vertices();
+	}
+
+	fn remove_ring(&mut self, r: usize) {
+		for index in self.vertices[r].clone().into_iter().rev() {
+			self.remove_line(index);
+		}
+		self.find_vertices();
+	}
+}
+
+impl std::fmt::Display for PointsText {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(f, "{}", self.lines.join("\n"))?;
+		if self.trailing_newline {
+			writeln!(f)?;
+		}
+		Ok(())
+	}
+}
+
+// Editing of `PolygonShader::points` by mouse: click adds a vertex to the nearest edge, drag moves a vertex, right click deletes it
+#[derive(Default)]
+struct VertexEditor {
+	enabled: bool,
+	dragging: Option<(usize, usize)>,
+}
+
+impl VertexEditor {
+	const PICK_DISTANCE: f32 = 10.;
+
+	fn rings(shader: &PolygonShader) -> Option<Vec<LineString<f64>>> {
+		match parse_rings(&shader.points) {
+			Ok(rings) => Some(rings),
+			Err(ParseError {
+				kind: ParseErrorKind::NoPoints,
+				..
+			}) => Some(Vec::new()),
+			Err(_) => None,
+		}
+	}
+
+	fn nearest_vertex(rings: &[LineString<f64>], mapping: &PlaneMapping) -> Option<(usize, usize)> {
+		let mouse = Vec2::from(mouse_position());
+		rings
+			.iter()
+			.enumerate()
+			.flat_map(|(r, ring)| ring.0.iter().enumerate().map(move |(i, c)| ((r, i), *c)))
+			.filter_map(|(index, c)| {
+				mapping
+					.polygon_to_screen((c.x, c.y))
+					.map(|pos| (index, (pos - mouse).length()))
+			})
+			.filter(|(_, distance)| *distance < Self::PICK_DISTANCE)
+			.min_by(|a, b| a.1.partial_cmp(&b.1).unwrap())
+			.map(|(index, _)| index)
+	}
+
+	// Edge with the nearest middle point, new vertex is inserted after its start
+	fn nearest_edge(rings: &[LineString<f64>], mapping: &PlaneMapping) -> Option<(usize, usize)> {
+		let mouse = Vec2::from(mouse_position());
+		rings
+			.iter()
+			.enumerate()
+			.flat_map(|(r, ring)| {
+				let len = ring.0.len();
+				(0..len).map(move |i| ((r, i), ring.0[i], ring.0[(i + 1) % len]))
+			})
+			.filter_map(|(index, a, b)| {
+				mapping
+					.polygon_to_screen(((a.x + b.x) / 2., (a.y + b.y) / 2.))
+					.map(|pos| (index, (pos - mouse).length()))
+			})
+			.min_by(|a, b| a.1.partial_cmp(&b.1).unwrap())
+			.map(|(index, _)| index)
+	}
+
+	// Coordinates are rounded to a thousandth of the polygon size, so the text stays readable at any scale
+	fn round(x: f64, size: f64) -> f64 {
+		let size = if size > 0. { size } else { 1. };
+		let exponent = size.log10().floor() as i32 - 3;
+		if exponent < 0 {
+			let scale = 10f64.powi(-exponent);
+			(x * scale).round() / scale
+		} else {
+			let scale = 10f64.powi(exponent);
+			(x / scale).round() * scale
+		}
+	}
+
+	// Returns true when points are changed
+	fn process(&mut self, shader: &mut PolygonShader, mapping: &PlaneMapping) -> bool {
+		let mut rings = match Self::rings(shader) {
+			Some(rings) => rings,
+			None => {
+				self.dragging = None;
+				return false;
+			},
+		};
+		let mut text = PointsText::new(&shader.points);
+		let size = mapping.size.0.max(mapping.size.1) as f64;
+		let mouse = mapping
+			.screen_to_polygon(mouse_position())
+			.map(|(x, y)| Coordinate {
+				x: Self::round(x, size),
+				y: Self::round(y, size),
+			});
+
+		let mut changed = false;
+		if is_mouse_button_pressed(MouseButton::Left) {
+			self.dragging = Self::nearest_vertex(&rings, mapping);
+			if let (None, Some(mouse)) = (self.dragging, mouse) {
+				match Self::nearest_edge(&rings, mapping) {
+					Some((r, i)) => {
+						rings[r].0.insert(i + 1, mouse);
+						text.insert((r, i), mouse);
+						self.dragging = Some((r, i + 1));
+					},
+					None => {
+						let d = Self::round(size * 0.05, size);
+						let ring = vec![
+							mouse,
+							Coordinate {
+								x: mouse.x + d,
+								y: mouse.y,
+							},
+							Coordinate {
+								x: mouse.x,
+								y: mouse.y + d,
+							},
+						];
+						text.push_ring(&ring);
+						rings.push(LineString(ring));
+					},
+				}
+				changed = true;
+			}
+		} else if is_mouse_button_down(MouseButton::Left) {
+			if let (Some((r, i)), Some(mouse)) = (self.dragging, mouse) {
+				if rings[r].0[i] != mouse {
+					rings[r].0[i] = mouse;
+					text.set((r, i), mouse);
+					changed = true;
+				}
+			}
+		} else {
+			self.dragging = None;
+		}
+
+		if is_mouse_button_pressed(MouseButton::Right) {
+			if let Some((r, i)) = Self::nearest_vertex(&rings, mapping) {
+				rings[r].0.remove(i);
+				text.remove((r, i));
+				if rings[r].0.len() < 3 {
+					rings.remove(r);
+					text.remove_ring(r);
+				}
+				self.dragging = None;
+				changed = true;
+			}
+		}
+
+		if changed {
+			shader.points = text.to_string();
+			shader.update_points = true;
+		}
+		changed
+	}
+
+	fn draw(&self, shader: &PolygonShader, mapping: &PlaneMapping) {
+		let rings = match Self::rings(shader) {
+			Some(rings) => rings,
+			None => return,
+		};
+		for (r, ring) in rings.iter().enumerate() {
+			for (i, coord) in ring.0.iter().enumerate() {
+				if let Some(pos) = mapping.polygon_to_screen((coord.x, coord.y)) {
+					let color = if self.dragging == Some((r, i)) {
+						ORANGE
+					} else {
+						RED
+					};
+					draw_circle(pos.x, pos.y, 4., color);
+				}
+			}
+		}
+	}
+}
+
+// Points file of the demo, generated code is exported next to it
+struct PolygonFile {
+	path: String,
+	status: String,
+}
+
+impl PolygonFile {
+	#[cfg(feature = "svg")]
+	const SVG_TOLERANCE: f64 = 0.01;
+
+	fn new(path: String) -> Self {
+		Self {
+			path,
+			status: String::new(),
+		}
+	}
+
+	// Text of points in the format of the editbox, other formats are converted by the library
+	fn read(&self) -> Result<String, String> {
+		#[cfg(feature = "svg")]
+		{
+			if self.path.ends_with(".svg") {
+				return svg::from_svg_file(&self.path, Self::SVG_TOLERANCE)
+					.map(|polygon| format_rings(&rings(&polygon)))
+					.map_err(|err| format!("{}: {}", self.path, err));
+			}
+		}
+
+		let text = fs::read_to_string(&self.path)
+			.map_err(|err| format!("Can't read `{}`: {}", self.path, err))?;
+
+		#[cfg(feature = "geojson")]
+		{
+			if self.path.ends_with(".geojson") || self.path.ends_with(".json") {
+				return geojson::from_geojson(&text)
+					.map(|polygon| format_rings(&rings(&polygon)))
+					.map_err(|err| format!("{}: {}", self.path, err));
+			}
+		}
+
+		if self.path.ends_with(".wkt") {
+			wkt::from_wkt(&text)
+				.map(|polygon| format_rings(&rings(&polygon)))
+				.map_err(|err| format!("{}: {}", self.path, err))
+		} else {
+			parse_rings(&text).map_err(|err| format!("{}:{}", self.path, err))?;
+			Ok(text)
+		}
+	}
+
+	fn open(&mut self, shader: &mut PolygonShader) {
+		match self.read() {
+			Ok(points) => {
+				shader.points = points;
+				shader.update_points = true;
+				self.status = format!("Opened `{}`", self.path);
+			},
+			Err(err) => shader.error = Some(err),
+		}
+	}
+
+	// Files of other formats are not overwritten with points, they are saved as `.txt` next to them and the file is switched to it
+	fn save(&mut self, shader: &mut PolygonShader) {
+		let converted = [".svg", ".geojson", ".json", ".wkt"]
+			.iter()
+			.any(|extension| self.path.ends_with(extension));
+		let path = if converted {
+			Path::new(&self.path)
+				.with_extension("txt")
+				.display()
+				.to_string()
+		} else {
+			self.path.clone()
+		};
+		match fs::write(&path, &shader.points) {
+			Ok(()) => {
+				self.status = format!("Saved `{}`", path);
+				self.path = path;
+			},
+			Err(err) => shader.error = Some(format!("Can't write `{}`: {}", path, err)),
+		}
+	}
+
+	// Writes code, binary tree and its picture with the name of the points file
+	fn export(&mut self, shader: &mut PolygonShader) {
+		let path = Path::new(&self.path);
+		let files: [(PathBuf, Vec<u8>); 3] = [
+			(
+				path.with_extension("glsl"),
+				shader.text.clone().into_bytes(),
+			),
+			(path.with_extension("fppg"), shader.calculated.to_bytes()),
+			(
+				path.with_extension("tree.svg"),
+				shader
+					.calculated
+					.to_svg(Some(&shader.polygon), &[], 1000)
+					.into_bytes(),
+			),
+		];
+		for (file, content) in files.iter() {
+			if let Err(err) = fs::write(file, content) {
+				shader.error = Some(format!("Can't write `{}`: {}", file.display(), err));
+				return;
+			}
+		}
+		self.status = format!("Exported `{}`", files[0].0.display());
+	}
+}
+
+fn rings(polygon: &MultiPolygon<f64>) -> Vec<LineString<f64>> {
+	polygon
+		.0
+		.iter()
+		.map(|poly| std::iter::once(poly.exterior()).chain(poly.interiors().iter()))
+		.flatten()
+		.cloned()
+		.collect()
+}
+
+// Compares frame time of the tree with the winding number over all edges in a uniform array, that's how point in polygon is usually done in a shader, edges of large polygons are in a constant array instead. Winding number is checked with the fill rule of the tree, so both give the same picture. The whole screen is drawn several times per frame with one of them in turns
+struct Benchmark {
+	enabled: bool,
+	// Polygon and fill rule of `naive` material
+	polygon: MultiPolygon<f64>,
+	fill_rule: FillRule,
+	naive: Option<Material>,
+	frame: usize,
+	// Sum of frame times of the current round, for the tree and for the winding number
+	times: [f32; 2],
+	result: String,
+	// Shown with the result when edges don't fit into the uniform array
+	note: String,
+}
+
+impl Benchmark {
+	const FRAMES: usize = 60;
+	// GLSL ES 1.00 guarantees only 128 uniform vectors, and other uniforms of the shader take some of them
+	const MAX_UNIFORM_EDGES: usize = 120;
+	const PASSES: usize = 16;
+
+	fn new() -> Self {
+		Self {
+			enabled: false,
+			polygon: MultiPolygon(vec![]),
+			fill_rule: FillRule::NonZero,
+			naive: None,
+			frame: 0,
+			times: [0., 0.],
+			result: String::new(),
+			note: String::new(),
+		}
+	}
+
+	fn toggle(&mut self) {
+		self.enabled = !self.enabled;
+		self.frame = 0;
+		self.times = [0., 0.];
+		self.result = "measuring...".to_owned();
+	}
+
+	fn current(&self) -> usize {
+		self.frame / Self::FRAMES % 2
+	}
+
+	fn material(&mut self, shader: &PolygonShader) -> Result<Material, Error> {
+		if self.current() == 0 {
+			return Ok(shader.material.clone());
+		}
+		match &self.naive {
+			Some(material)
+				if self.polygon == shader.polygon
+					&& self.fill_rule == shader.calculated.fill_rule =>
+			{
+				Ok(material.clone())
+			},
+			_ => {
+				let method = ReferenceMethod::WindingNumber(shader.calculated.fill_rule);
+				let edges = reference_edges(&shader.polygon)
+					.iter()
+					.map(|edge| {
+						Vec4::new(
+							edge.start.x as f32,
+							edge.start.y as f32,
+							edge.end.x as f32,
+							edge.end.y as f32,
+						)
+					})
+					.collect::<Vec<_>>();
+				// Larger polygons are checked with the constant array, it's filled on every call, so it's slower
+				let uniform = !edges.is_empty() && edges.len() <= Self::MAX_UNIFORM_EDGES;
+				let (code, uniforms) = if uniform {
+					(
+						reference_uniform_code("polygon", &shader.polygon, method),
+						vec![UniformDesc::new("edges_polygon", UniformType::Float4)
+							.array(edges.len())],
+					)
+				} else {
+					// Empty array is not declared by the code
+					(
+						reference_code("polygon", &shader.polygon, method, Language::Glsl),
+						vec![],
+					)
+				};
+				self.note = if edges.len() > Self::MAX_UNIFORM_EDGES {
+					format!(
+						"{} edges don't fit into\n{} uniforms, winding number\nuses a constant array",
+						edges.len(),
+						Self::MAX_UNIFORM_EDGES
+					)
+				} else {
+					String::new()
+				};
+				let material = PolygonShader::load_material(&shader.calculated, &code, uniforms)?;
+				if uniform {
+					material.set_uniform_array("edges_polygon", &edges);
+				}
+				self.polygon = shader.polygon.clone();
+				self.fill_rule = shader.calculated.fill_rule;
+				self.naive = Some(material.clone());
+				Ok(material)
+			},
+		}
+	}
+
+	// Called after the frame is drawn, but `get_frame_time` is the time of the previous frame, so the first frame of each turn is skipped
+	fn measure(&mut self) {
+		if self.frame % Self::FRAMES != 0 {
+			self.times[self.current()] += get_frame_time();
+		}
+		self.frame += 1;
+		if self.frame % (2 * Self::FRAMES) == 0 {
+			let average = |time: f32| time / (Self::FRAMES - 1) as f32 * 1000.;
+			self.result = format!(
+				"tree: {:.2} ms\nwinding number: {:.2} ms\n{} passes per frame",
+				average(self.times[0]),
+				average(self.times[1]),
+				Self::PASSES
+			);
+			self.times = [0., 0.];
+		}
+	}
+}
+
+struct PolygonShader {
+	points: String,
+	update_points: bool,
+	show_grid: bool,
+	show_tree: bool,
+	fill_rule: FillRule,
+	polygon: MultiPolygon<f64>,
+	calculated: PolygonFastPrecalculator,
+	error: Option<String>,
+	text: String,
+	stats: String,
+	// Split lines of the tree in coordinates of the polygon, they are drawn above the shader when `show_tree` is enabled
+	splits: Vec<Line<f64>>,
+	material: Material,
+	offset: (f32, f32),
+	size: (f32, f32),
+}
+
+impl PolygonShader {
+	const FRAGMENT_SHADER_AFTER: &'static str = include_str!("frag_after.glsl");
+	const FRAGMENT_SHADER_BEFORE: &'static str = include_str!("frag_before.glsl");
+	const VERTEX_SHADER: &'static str = include_str!("vertex.glsl");
+
+	fn new(init: Vec<(f64, f64)>) -> Self {
+		let points = init
+			.iter()
+			.map(|(x, y)| format!("{} {}", x, y))
+			.collect::<Vec<String>>()
+			.join("\n");
+		let fill_rule = FillRule::default();
+		let polygon = vec_to_multipolygon(init);
+		let calculated = PolygonFastPrecalculator::calc_with_options(
+			"polygon".to_owned(),
+			polygon.clone(),
+			&Self::options(fill_rule),
+		);
+		let (material, offset, size, text, stats) = Self::calc_material(&calculated)
+			.unwrap_or_else(|err| {
+				if let Error::ShaderError(ShaderError::CompilationError { error_message, .. }) = err
+				{
+					println!("Fragment shader compilation error:\n{}", error_message);
+				} else {
+					println!("Other material error:\n{:#?}", err);
+				}
+				std::process::exit(1)
+			});
+		let splits = Self::splits(&calculated);
+		Self {
+			points,
+			update_points: false,
+			show_grid: false,
+			show_tree: false,
+			fill_rule,
+			polygon,
+			calculated,
+			error: None,
+			text,
+			stats,
+			splits,
+			material,
+			size,
+			offset,
+		}
+	}
+
+	fn options(fill_rule: FillRule) -> CalcOptions {
+		CalcOptions {
+			fill_rule,
+			..Default::default()
+		}
+	}
+
+	fn splits(calculated: &PolygonFastPrecalculator) -> Vec<Line<f64>> {
+		calculated
+			.cells()
+			.iter()
+			.filter_map(|cell| cell.split_line(calculated))
+			.collect()
+	}
+
+	fn calc_material(
+		calculated: &PolygonFastPrecalculator,
+	) -> Result<(Material, (f32, f32), (f32, f32), String, String), Error> {
+		let offset = (
+			calculated.bounding_rect.min().x as f32,
+			calculated.bounding_rect.min().y as f32,
+		);
+		let size = (
+			calculated.bounding_rect.width() as f32,
+			calculated.bounding_rect.height() as f32,
+		);
+		let text = format!("{}", calculated);
+		let material = Self::load_material(calculated, &text, vec![])?;
+		Ok((material, offset, size, text, calculated.stats().to_string()))
+	}
+
+	// Material with `is_inside_polygon` from `code` and its `uniforms`, the tree is used only for the overlay
+	fn load_material(
+		calculated: &PolygonFastPrecalculator,
+		code: &str,
+		uniforms: Vec<UniformDesc>,
+	) -> Result<Material, Error> {
+		load_material(
+			ShaderSource::Glsl {
+				vertex: Self::VERTEX_SHADER,
+				fragment: &format!(
+					"{}\n{}\n{}\nconst float tree_max_depth = {}.;\n{}",
+					Self::FRAGMENT_SHADER_BEFORE,
+					code,
+					calculated.leaf_code(),
+					calculated.stats().max_depth,
+					Self::FRAGMENT_SHADER_AFTER
+				),
+			},
+			MaterialParams {
+				uniforms: vec![
+					UniformDesc::new("camera", UniformType::Mat4),
+					UniformDesc::new("resolution", UniformType::Float2),
+					UniformDesc::new("offset", UniformType::Float2),
+					UniformDesc::new("size", UniformType::Float2),
+					UniformDesc::new("show_grid", UniformType::Int1),
+					UniformDesc::new("show_tree", UniformType::Int1),
+					UniformDesc::new("ortho", UniformType::Int1),
+					UniformDesc::new("view_center", UniformType::Float2),
+					UniformDesc::new("view_zoom", UniformType::Float1),
+				]
+				.into_iter()
+				.chain(uniforms)
+				.collect(),
+				..Default::default()
+			},
+		)
+	}
+
+	fn update(&mut self) {
+		if self.update_points {
+			match parse_multipolygon(&self.points) {
+				Ok(polygon) => {
+					// Only parts of the tree near the edited vertices are recalculated
+					self.calculated = self.calculated.recalc(
+						&self.polygon,
+						polygon.clone(),
+						&Self::options(self.fill_rule),
+					);
+					self.polygon = polygon;
+					let (material, offset, size, text, stats) =
+						Self::calc_material(&self.calculated).unwrap();
+					self.material = material;
+					self.offset = offset;
+					self.size = size;
+					self.text = text;
+					self.stats = stats;
+					self.splits = Self::splits(&self.calculated);
+					self.error = None;
+				},
+				Err(err) => {
+					self.error = Some(err.to_string());
+				},
+			}
+			self.update_points = false;
+		}
+	}
+}
+
+fn window_conf() -> Conf {
+	Conf {
+		window_title: "Precalculate polygon".to_owned(),
+		high_dpi: true,
+		..Default::default()
+	}
+}
+
+#[macroquad::main(window_conf)]
+async fn main() {
+	let mut cam = RotateAroundCam::new();
+	let mut ortho_cam = OrthoCam::new();
+	let mut ortho = false;
+	let mut cursor = String::new();
+	let mut benchmark = Benchmark::new();
+
+	let init = vec![
+		// This is complex self-intersecting polygon, this not work with `geo_booleanop`
+		(3.1, 3.4),
+		(29.0, 10.0),
+		(0.0, 19.9),
+		(16.5, 31.3),
+		(27.1, 22.6),
+		(17.7, 0.0),
+		(16.7, 24.0),
+		(5.5, 10.8),
+		(19.4, 11.3),
+		
+		// (0., 8.),
+		// (5., 6.),
+		// (2., 4.),
+		// (7., 2.),
+		// (0., -1.),
+		// (-7., 2.),
+		// (-2., 4.),
+		// (-5., 6.),
+	];
+
+	let mut shader = PolygonShader::new(init);
+	// File passed as an argument is opened at start, files dropped into the window are opened in the loop
+	let mut file = PolygonFile::new(
+		std::env::args()
+			.nth(1)
+			.unwrap_or_else(|| "polygon.txt".to_owned()),
+	);
+	if std::env::args().nth(1).is_some() {
+		file.open(&mut shader);
+	}
+	let mut editor = VertexEditor::default();
+	let mut closed = false;
+	// View is not changed while a vertex is dragged, otherwise the polygon would run away from the cursor when its bounding rect changes
+	let mut view = (shader.offset, shader.size);
+
+	loop {
+		// Files dropped into the window, the last of them is opened
+		if let Some(path) = get_dropped_files()
+			.into_iter()
+			.filter_map(|dropped| dropped.path)
+			.last()
+		{
+			file.path = path.display().to_string();
+			file.open(&mut shader);
+			closed = false;
+		}
+
+		Window::new(hash!(), vec2(20., 20.), vec2(210., 270.))
+			.label("Coordinates")
+			.ui(&mut *root_ui(), |ui| {
+				ui.editbox(hash!(), vec2(200., 200.), &mut shader.points);
+				if ui.button(None, "Update") {
+					shader.update_points = true;
+					closed = false;
+				}
+				ui.same_line(0.0);
+				if ui.button(None, "Show grid") {
+					shader.show_grid = !shader.show_grid;
+				}
+				ui.same_line(0.0);
+				if ui.button(None, "Show tree") {
+					shader.show_tree = !shader.show_tree;
+				}
+				if ui.button(None, shader.fill_rule.name()) {
+					let pos = FillRule::ALL
+						.iter()
+						.position(|rule| *rule == shader.fill_rule)
+						.unwrap();
+					shader.fill_rule = FillRule::ALL[(pos + 1) % FillRule::ALL.len()];
+					shader.update_points = true;
+				}
+				ui.same_line(0.0);
+				if ui.button(None, if editor.enabled { "Stop edit" } else { "Edit" }) {
+					editor.enabled = !editor.enabled;
+				}
+				ui.same_line(0.0);
+				if ui.button(None, if ortho { "3D" } else { "2D" }) {
+					ortho = !ortho;
+				}
+				if ui.button(None, "Benchmark") {
+					benchmark.toggle();
+				}
+			});
+
+		if let Some(mut error) = shader.error.clone() {
+			if !closed {
+				closed = !Window::new(hash!(), vec2(200., 200.), vec2(400., 100.))
+					.label("Error message")
+					.close_button(true)
+					.ui(&mut *root_ui(), |ui| {
+						ui.editbox(hash!(), vec2(495., 280.), &mut error);
+					});
+			}
+		} else {
+			closed = false;
+		}
+
+		Window::new(hash!(), vec2(250., 20.), vec2(300., 90.))
+			.label("File")
+			.ui(&mut *root_ui(), |ui| {
+				ui.editbox(hash!(), vec2(290., 20.), &mut file.path);
+				if ui.button(None, "Open") {
+					file.open(&mut shader);
+					closed = false;
+				}
+				ui.same_line(0.0);
+				if ui.button(None, "Save") {
+					file.save(&mut shader);
+					closed = false;
+				}
+				ui.same_line(0.0);
+				if ui.button(None, "Export") {
+					file.export(&mut shader);
+					closed = false;
+				}
+				ui.label(None, &file.status);
+			});
+
+		Window::new(hash!(), vec2(570., 20.), vec2(200., 90.))
+			.label("Cursor")
+			.ui(&mut *root_ui(), |ui| {
+				for line in cursor.lines() {
+					ui.label(None, line);
+				}
+			});
+
+		if benchmark.enabled {
+			Window::new(hash!(), vec2(790., 20.), vec2(200., 150.))
+				.label("Benchmark")
+				.ui(&mut *root_ui(), |ui| {
+					for line in benchmark.result.lines().chain(benchmark.note.lines()) {
+						ui.label(None, line);
+					}
+				});
+		}
+
+		Window::new(hash!(), vec2(20., 310.), vec2(500., 300.))
+			.label("Shader code")
+			.ui(&mut *root_ui(), |ui| {
+				ui.editbox(hash!(), vec2(495., 280.), &mut shader.text);
+			});
+
+		Window::new(hash!(), vec2(540., 310.), vec2(400., 100.))
+			.label("Statistics")
+			.ui(&mut *root_ui(), |ui| {
+				for line in shader.stats.lines() {
+					ui.label(None, line);
+				}
+			});
+
+		let mouse_over_canvas = !root_ui().is_mouse_over(Vec2::from(mouse_position()));
+
+		if editor.dragging.is_none() {
+			view = (shader.offset, shader.size);
+		}
+		let mapping = PlaneMapping {
+			projection: if ortho {
+				Projection::Orthographic(ortho_cam)
+			} else {
+				Projection::Perspective(cam.get_matrix())
+			},
+			offset: view.0,
+			size: view.1,
+		};
+
+		// Shown in the next frame, CPU result of the tree is compared with the original polygon
+		cursor = match mapping.screen_to_polygon(mouse_position()) {
+			Some((x, y)) if mouse_over_canvas => {
+				let point = Point::new(x, y);
+				let name = |inside| if inside { "inside" } else { "outside" };
+				format!(
+					"{:.4} {:.4}\ntree: {}\npolygon: {}",
+					x,
+					y,
+					name(shader.calculated.is_inside(point)),
+					name(shader.calculated.fill_rule.contains(&shader.polygon, point))
+				)
+			},
+			_ => String::new(),
+		};
+
+		if mouse_over_canvas {
+			if ortho {
+				ortho_cam.process_mouse_and_keys(!editor.enabled);
+			} else {
+				cam.process_mouse_and_keys(!editor.enabled);
+			}
+			if editor.enabled && editor.process(&mut shader, &mapping) {
+				closed = false;
+			}
+		}
+
+		let material = if benchmark.enabled {
+			benchmark.material(&shader).unwrap_or_else(|err| {
+				shader.error = Some(format!("{:?}", err));
+				benchmark.enabled = false;
+				shader.material.clone()
+			})
+		} else {
+			shader.material.clone()
+		};
+		material.set_uniform("resolution", (screen_width(), screen_height()));
+		material.set_uniform("camera", cam.get_matrix());
+		material.set_uniform("ortho", ortho as i32);
+		material.set_uniform("view_center", (ortho_cam.center.x, ortho_cam.center.y));
+		material.set_uniform("view_zoom", ortho_cam.zoom);
+		material.set_uniform("offset", mapping.offset);
+		material.set_uniform("size", mapping.size);
+		material.set_uniform("show_grid", shader.show_grid as i32);
+		material.set_uniform("show_tree", shader.show_tree as i32);
+
+		clear_background(BLACK);
+		gl_use_material(&material);
+		let passes = if benchmark.enabled {
+			Benchmark::PASSES
+		} else {
+			1
+		};
+		for _ in 0..passes {
+			draw_rectangle(0., 0., screen_width(), screen_height(), WHITE);
+		}
+		gl_use_default_material();
+		if shader.show_tree {
+			for line in &shader.splits {
+				let start = mapping.polygon_to_screen((line.start.x, line.start.y));
+				let end = mapping.polygon_to_screen((line.end.x, line.end.y));
+				if let (Some(start), Some(end)) = (start, end) {
+					draw_line(start.x, start.y, end.x, end.y, 1., DARKGRAY);
+				}
+			}
+		}
+		if editor.enabled {
+			editor.draw(&shader, &mapping);
+		}
+
+		shader.update();
+		if benchmark.enabled {
+			benchmark.measure();
+		}
+
+		next_frame().await;
+	}
+}
@@ -0,0 +1,495 @@
+use std::{fs, process::exit};
+
+use fast_point_in_polygon_for_glsl::*;
+use geo::{prelude::BoundingRect, MultiPolygon, Point};
+
+const USAGE: &str = "Usage: fppg generate <input> [options]
+
+Reads points `x y`, one point per line, empty line starts a new ring, or polygons of `.wkt` file,
+or paths of `.svg` file when built with `svg` feature, or polygons of `.geojson` file when built
+with `geojson` feature, or contours of `.png` mask when built with `bitmap` feature, or glyph of
+`.ttf` or `.otf` font when built with `font` feature, and writes code of `is_inside_<name>`
+function.
+
+Options:
+  -o, --output <file>       write code to file instead of stdout
+  --name <name>             name of function suffix, default is `polygon` or `glyph_<char>`
+  --target <target>         target language: glsl, or rust for `--reference`; default is glsl
+  --reference <method>      write straightforward crossing-number or winding-number function
+                            instead of the tree, winding number uses `--fill-rule`
+  --grid <size>             write function that checks only edges in the cell of size x size
+                            grid instead of the tree
+  --fill-rule <rule>        even-odd, nonzero, positive, negative; default is nonzero
+  --precision <precision>   round constants to f64, f32 or f16; default is f64
+  --precision-report <precision>
+                            print points where the tree evaluated in f32 or f16 gives another
+                            result than in f64 to stderr
+  --max-edges-per-leaf <n>  check parts of the polygon with at most n edges by crossing number
+                            instead of a subtree when it's cheaper; default is 0, disabled
+  --tolerance <value>       max deviation of flattened curves of svg input, default is 0.01;
+                            of simplified contours of png input in pixels, default is 0.5;
+                            for font input it's a fraction of the em size, default is 0.001
+  --threshold <value>       min alpha or brightness of pixels inside png mask, default is 128
+  --char <char>             character of font input
+  --verify <samples>        compare result with the original polygon, fail on mismatches
+  --verify-tolerance <value>
+                            ignore mismatches of `--verify` closer to the boundary than this
+                            fraction of the largest side of the polygon, default is 0.001
+  --stats                   print statistics of the tree to stderr
+  --draw <file>             draw the tree to `.svg` file, or to `.png` file with `debug-image`
+                            feature, mismatches of `--verify` are marked red
+  --cells <file>            write cells of the tree as GeoJSON, needs `geojson` feature
+  --sdf <file>              write signed distance field of the polygon to `.png` file and
+                            sampler function of it instead of the tree, needs `sdf` feature
+  --sdf-size <pixels>       largest side of the distance field, default is 256
+  --sdf-spread <value>      max distance in the distance field, fraction of the largest side of
+                            the polygon, default is 0.05
+  -h, --help                print this message";
+
+const PRECISION_REPORT_SAMPLES: usize = 10000;
+
+struct Args {
+	input: String,
+	output: Option<String>,
+	name: String,
+	#[cfg_attr(not(feature = "font"), allow(dead_code))]
+	character: Option<char>,
+	options: CalcOptions,
+	target: Language,
+	reference: Option<ReferenceMethod>,
+	grid: Option<usize>,
+	#[cfg_attr(
+		not(any(feature = "svg", feature = "bitmap", feature = "font")),
+		allow(dead_code)
+	)]
+	// Default depends on the input format
+	tolerance: Option<f64>,
+	#[cfg_attr(not(feature = "bitmap"), allow(dead_code))]
+	threshold: u8,
+	verify: Option<usize>,
+	verify_tolerance: f64,
+	precision_report: Option<Precision>,
+	stats: bool,
+	cells: Option<String>,
+	draw: Option<String>,
+	sdf: Option<String>,
+	#[cfg_attr(not(feature = "sdf"), allow(dead_code))]
+	sdf_size: usize,
+	#[cfg_attr(not(feature = "sdf"), allow(dead_code))]
+	sdf_spread: f64,
+}
+
+// `None` when help is requested
+fn parse_args(args: Vec<String>) -> Result<Option<Args>, String> {
+	let mut args = args.into_iter();
+	match args.next().as_deref() {
+		Some("generate") => {},
+		Some("-h") | Some("--help") | None => return Ok(None),
+		Some(command) => return Err(format!("Unknown command `{}`", command)),
+	}
+
+	let mut input = None;
+	let mut output = None;
+	let mut name = None;
+	let mut character = None;
+	let mut options = CalcOptions::default();
+	let mut target = Language::Glsl;
+	let mut reference = None;
+	let mut grid = None;
+	let mut tolerance = None;
+	let mut threshold = 128;
+	let mut verify = None;
+	let mut verify_tolerance = 0.001;
+	let mut precision_report = None;
+	let mut stats = false;
+	let mut cells = None;
+	let mut draw = None;
+	let mut sdf = None;
+	let mut sdf_size = 256;
+	let mut sdf_spread = 0.05;
+	while let Some(arg) = args.next() {
+		let mut value = || {
+			args.next()
+				.ok_or_else(|| format!("Value for `{}` is not specified", arg))
+		};
+		match arg.as_str() {
+			"-o" | "--output" => output = Some(value()?),
+			"--name" => name = Some(value()?),
+			"--char" => {
+				let value = value()?;
+				let mut chars = value.chars();
+				character = match (chars.next(), chars.next()) {
+					(Some(c), None) => Some(c),
+					_ => return Err(format!("`{}` is not a single character", value)),
+				};
+			},
+			"--target" => {
+				target = match value()?.as_str() {
+					"glsl" => Language::Glsl,
+					"rust" => Language::Rust,
+					target => return Err(format!("Unknown target `{}`", target)),
+				}
+			},
+			"--reference" => {
+				let method = value()?;
+				if method != "crossing-number" && method != "winding-number" {
+					return Err(format!("Unknown reference method `{}`", method));
+				}
+				reference = Some(method);
+			},
+			"--grid" => {
+				let value = value()?;
+				grid = match value.parse() {
+					Ok(0) | Err(_) => return Err(format!("Can't parse grid size `{}`", value)),
+					Ok(size) => Some(size),
+				};
+			},
+			"--fill-rule" => {
+				let rule = value()?;
+				options.fill_rule = *FillRule::ALL
+					.iter()
+					.find(|x| x.name() == rule)
+					.ok_or_else(|| format!("Unknown fill rule `{}`", rule))?;
+			},
+			"--precision" => options.constants_precision = parse_precision(&value()?)?,
+			"--precision-report" => {
+				precision_report = match parse_precision(&value()?)? {
+					Precision::F64 => {
+						return Err("Precision of `--precision-report` is f32 or f16".to_owned())
+					},
+					precision => Some(precision),
+				};
+			},
+			"--max-edges-per-leaf" => {
+				let value = value()?;
+				options.max_edges_per_leaf = value
+					.parse()
+					.map_err(|_| format!("Can't parse max edges per leaf `{}`", value))?;
+			},
+			"--tolerance" => {
+				let value = value()?;
+				tolerance = Some(
+					value
+						.parse()
+						.map_err(|_| format!("Can't parse tolerance `{}`", value))?,
+				);
+			},
+			"--threshold" => {
+				let value = value()?;
+				threshold = value
+					.parse()
+					.map_err(|_| format!("Can't parse threshold `{}`", value))?;
+			},
+			"--verify" => {
+				let samples = value()?;
+				verify = Some(
+					samples
+						.parse()
+						.map_err(|_| format!("Can't parse samples count `{}`", samples))?,
+				);
+			},
+			"--verify-tolerance" => {
+				let value = value()?;
+				verify_tolerance = value
+					.parse()
+					.map_err(|_| format!("Can't parse verify tolerance `{}`", value))?;
+			},
+			"-h" | "--help" => return Ok(None),
+			"--stats" => stats = true,
+			"--cells" => cells = Some(value()?),
+			"--draw" => draw = Some(value()?),
+			"--sdf" => sdf = Some(value()?),
+			"--sdf-size" => {
+				let value = value()?;
+				sdf_size = match value.parse() {
+					Ok(0) | Err(_) => {
+						return Err(format!("Can't parse distance field size `{}`", value))
+					},
+					Ok(size) => size,
+				};
+			},
+			"--sdf-spread" => {
+				let value = value()?;
+				sdf_spread = match value.parse::<f64>() {
+					Ok(spread) if spread > 0. => spread,
+					_ => return Err(format!("Can't parse distance field spread `{}`", value)),
+				};
+			},
+			_ if arg.starts_with('-') => return Err(format!("Unknown option `{}`", arg)),
+			_ if input.is_none() => input = Some(arg),
+			_ => return Err(format!("Unexpected argument `{}`", arg)),
+		}
+	}
+
+	let reference = reference.map(|method| {
+		if method == "crossing-number" {
+			ReferenceMethod::CrossingNumber
+		} else {
+			ReferenceMethod::WindingNumber(options.fill_rule)
+		}
+	});
+	if target == Language::Rust && reference.is_none() {
+		return Err("Target `rust` is supported only with `--reference`".to_owned());
+	}
+	if reference.is_some() && grid.is_some() {
+		return Err("`--reference` and `--grid` can't be used together".to_owned());
+	}
+	// These need the tree, which is not built for `--reference` and `--grid`
+	let tree_options = [
+		("--stats", stats),
+		("--draw", draw.is_some()),
+		("--cells", cells.is_some()),
+		("--sdf", sdf.is_some()),
+		("--precision-report", precision_report.is_some()),
+	];
+	let without_tree = if reference.is_some() {
+		Some("--reference")
+	} else if grid.is_some() {
+		Some("--grid")
+	} else {
+		None
+	};
+	if let (Some(without_tree), Some((option, _))) =
+		(without_tree, tree_options.iter().find(|(_, used)| *used))
+	{
+		return Err(format!(
+			"`{}` can't be used with `{}`",
+			option, without_tree
+		));
+	}
+
+	#[cfg(feature = "font")]
+	let default_name = character.map(font::glyph_name);
+	#[cfg(not(feature = "font"))]
+	let default_name = None;
+
+	Ok(Some(Args {
+		input: input.ok_or_else(|| "Input file is not specified".to_owned())?,
+		output,
+		name: name
+			.or(default_name)
+			.unwrap_or_else(|| "polygon".to_owned()),
+		character,
+		options,
+		target,
+		reference,
+		grid,
+		tolerance,
+		threshold,
+		verify,
+		verify_tolerance,
+		precision_report,
+		stats,
+		cells,
+		draw,
+		sdf,
+		sdf_size,
+		sdf_spread,
+	}))
+}
+
+fn parse_precision(value: &str) -> Result<Precision, String> {
+	match value {
+		"f64" => Ok(Precision::F64),
+		"f32" => Ok(Precision::F32),
+		"f16" => Ok(Precision::F16),
+		precision => Err(format!("Unknown precision `{}`", precision)),
+	}
+}
+
+fn read_polygon(args: &Args) -> Result<MultiPolygon<f64>, String> {
+	#[cfg(feature = "svg")]
+	{
+		if args.input.ends_with(".svg") {
+			return svg::from_svg_file(&args.input, args.tolerance.unwrap_or(0.01))
+				.map_err(|err| format!("{}: {}", args.input, err));
+		}
+	}
+
+	#[cfg(feature = "bitmap")]
+	{
+		if args.input.ends_with(".png") {
+			return bitmap::from_png_mask(
+				&args.input,
+				args.threshold,
+				args.tolerance.unwrap_or(0.5),
+			)
+			.map_err(|err| format!("{}: {}", args.input, err));
+		}
+	}
+
+	#[cfg(feature = "font")]
+	{
+		if args.input.ends_with(".ttf") || args.input.ends_with(".otf") {
+			let c = args
+				.character
+				.ok_or_else(|| "Character of font input is not specified".to_owned())?;
+			let font = fs::read(&args.input)
+				.map_err(|err| format!("Can't read `{}`: {}", args.input, err))?;
+			// Contours are combined by `--fill-rule`, nonzero by default as fonts are rendered
+			return font::glyph_contours(&font, c, args.tolerance.unwrap_or(0.001))
+				.map_err(|err| format!("{}: {}", args.input, err));
+		}
+	}
+
+	let text = fs::read_to_string(&args.input)
+		.map_err(|err| format!("Can't read `{}`: {}", args.input, err))?;
+
+	#[cfg(feature = "geojson")]
+	{
+		if args.input.ends_with(".geojson") || args.input.ends_with(".json") {
+			return geojson::from_geojson(&text).map_err(|err| format!("{}: {}", args.input, err));
+		}
+	}
+
+	if args.input.ends_with(".wkt") {
+		wkt::from_wkt(&text).map_err(|err| format!("{}: {}", args.input, err))
+	} else {
+		parse_multipolygon(&text).map_err(|err| format!("{}:{}", args.input, err))
+	}
+}
+
+fn draw_tree(
+	calculated: &PolygonFastPrecalculator,
+	polygon: &MultiPolygon<f64>,
+	points: &[Point<f64>],
+	file: &str,
+) -> Result<(), String> {
+	const SIZE: usize = 1000;
+
+	if file.ends_with(".png") {
+		#[cfg(feature = "debug-image")]
+		return calculated
+			.to_image(Some(polygon), points, SIZE)
+			.save(file)
+			.map_err(|err| format!("Can't write `{}`: {}", file, err));
+		#[cfg(not(feature = "debug-image"))]
+		return Err(format!(
+			"Can't write `{}`: built without `debug-image` feature",
+			file
+		));
+	}
+
+	fs::write(file, calculated.to_svg(Some(polygon), points, SIZE))
+		.map_err(|err| format!("Can't write `{}`: {}", file, err))
+}
+
+// Tree drops vertices that barely change the shape, so points that close to the boundary can mismatch
+fn verify_tolerance(fraction: f64, polygon: &MultiPolygon<f64>) -> f64 {
+	polygon
+		.bounding_rect()
+		.map(|rect| fraction * rect.width().max(rect.height()))
+		.unwrap_or(0.)
+}
+
+fn run(args: Args) -> Result<(), String> {
+	let polygon = read_polygon(&args)?;
+	// The tree is not built at all, it can be too big for this polygon
+	if let Some(method) = args.reference {
+		let code = reference_code(&args.name, &polygon, method, args.target);
+		return write_code(args.output, code);
+	}
+	if let Some(size) = args.grid {
+		let grid =
+			GridPrecalculator::calc(args.name, polygon.clone(), args.options.fill_rule, size);
+		if let Some(samples) = args.verify {
+			let report = grid.verify(&polygon, samples);
+			if !report.is_ok_with_tolerance(verify_tolerance(args.verify_tolerance, &polygon)) {
+				return Err(format!("Verification failed:\n{}", report));
+			}
+		}
+		return write_code(args.output, grid.to_string());
+	}
+
+	let calculated =
+		PolygonFastPrecalculator::calc_with_options(args.name, polygon.clone(), &args.options);
+
+	let report = args
+		.verify
+		.map(|samples| calculated.verify(&polygon, samples));
+
+	if let Some(draw) = &args.draw {
+		let points = report
+			.as_ref()
+			.map(|report| report.mismatched_points.as_slice())
+			.unwrap_or(&[]);
+		draw_tree(&calculated, &polygon, points, draw)?;
+	}
+
+	if let Some(report) = report {
+		if !report.is_ok_with_tolerance(verify_tolerance(args.verify_tolerance, &polygon)) {
+			return Err(format!("Verification failed:\n{}", report));
+		}
+	}
+
+	if args.stats {
+		eprint!("{}", calculated.stats());
+	}
+
+	if let Some(precision) = args.precision_report {
+		eprint!(
+			"{}",
+			calculated.precision_report(precision, PRECISION_REPORT_SAMPLES)
+		);
+	}
+
+	if let Some(cells) = &args.cells {
+		#[cfg(feature = "geojson")]
+		fs::write(cells, calculated.cells_to_geojson())
+			.map_err(|err| format!("Can't write `{}`: {}", cells, err))?;
+		#[cfg(not(feature = "geojson"))]
+		return Err(format!(
+			"Can't write `{}`: built without `geojson` feature",
+			cells
+		));
+	}
+
+	if let Some(sdf) = &args.sdf {
+		#[cfg(feature = "sdf")]
+		{
+			let field = calculated.distance_field(&polygon, args.sdf_size, args.sdf_spread);
+			field
+				.save(sdf)
+				.map_err(|err| format!("Can't write `{}`: {}", sdf, err))?;
+			return write_code(args.output, field.sampler_code());
+		}
+		#[cfg(not(feature = "sdf"))]
+		return Err(format!(
+			"Can't write `{}`: built without `sdf` feature",
+			sdf
+		));
+	}
+
+	write_code(args.output, calculated.to_string())
+}
+
+fn write_code(output: Option<String>, code: String) -> Result<(), String> {
+	match output {
+		Some(output) => {
+			fs::write(&output, code).map_err(|err| format!("Can't write `{}`: {}", output, err))
+		},
+		None => {
+			print!("{}", code);
+			Ok(())
+		},
+	}
+}
+
+fn main() {
+	let args = match parse_args(std::env::args().skip(1).collect()) {
+		Ok(Some(args)) => args,
+		Ok(None) => {
+			println!("{}", USAGE);
+			return;
+		},
+		Err(message) => {
+			eprintln!("{}\n\n{}", message, USAGE);
+			exit(2);
+		},
+	};
+
+	if let Err(message) = run(args) {
+		eprintln!("{}", message);
+		exit(1);
+	}
+}
@@ -0,0 +1,291 @@
+use std::{convert::TryInto, fmt};
+
+use geo::{Coordinate, Rect};
+
+use crate::{
+	fill_rule::FillRule, CrossingEdge, LineSplitCheck, LineSplitCheckGeneralized,
+	PolygonFastPrecalculator, PolygonFastPrecalculatorPart,
+};
+
+const MAGIC: &[u8; 4] = b"FPPG";
+
+/// Version of the binary format, increased on every incompatible change.
+pub const BINARY_VERSION: u16 = 2;
+
+/// Max depth of the tree that is read, real trees are much shallower. Reading is recursive, so it also keeps the stack of a debug build small enough.
+pub const MAX_DEPTH: usize = 256;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum BinaryError {
+	WrongMagic,
+	UnsupportedVersion(u16),
+	UnexpectedEnd,
+	InvalidTag(u8),
+	InvalidName,
+	TrailingBytes,
+	/// Tree is deeper than `MAX_DEPTH`, such data is corrupted or crafted to overflow the stack.
+	TooDeep,
+}
+
+impl fmt::Display for BinaryError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		use BinaryError::*;
+		match self {
+			WrongMagic => write!(f, "data is not a precalculated tree"),
+			UnsupportedVersion(version) => write!(
+				f,
+				"unsupported version {}, expected {}",
+				version, BINARY_VERSION
+			),
+			UnexpectedEnd => write!(f, "unexpected end of data"),
+			InvalidTag(tag) => write!(f, "invalid tag {}", tag),
+			InvalidName => write!(f, "name is not valid utf-8"),
+			TrailingBytes => write!(f, "unexpected data after the tree"),
+			TooDeep => write!(f, "tree is deeper than {}", MAX_DEPTH),
+		}
+	}
+}
+
+impl std::error::Error for BinaryError {}
+
+impl PolygonFastPrecalculator {
+	/// Compact little-endian format: `FPPG` magic, `u16` version, name, bounding rect, fill rule, and parts of the tree in prefix order.
+	pub fn to_bytes(&self) -> Vec<u8> {
+		let mut result = MAGIC.to_vec();
+		result.extend_from_slice(&BINARY_VERSION.to_le_bytes());
+		result.extend_from_slice(&(self.name.len() as u32).to_le_bytes());
+		result.extend_from_slice(self.name.as_bytes());
+		let (min, max) = (self.bounding_rect.min(), self.bounding_rect.max());
+		for value in &[min.x, min.y, max.x, max.y] {
+			result.extend_from_slice(&value.to_le_bytes());
+		}
+		result.push(
+			FillRule::ALL
+				.iter()
+				.position(|rule| *rule == self.fill_rule)
+				.unwrap() as u8,
+		);
+		write_part(&self.parts, &mut result);
+		result
+	}
+
+	pub fn from_bytes(bytes: &[u8]) -> Result<Self, BinaryError> {
+		let mut reader = Reader { bytes };
+		if reader.take(MAGIC.len())? != MAGIC {
+			return Err(BinaryError::WrongMagic);
+		}
+		let version = u16::from_le_bytes(reader.take(2)?.try_into().unwrap());
+		if version != BINARY_VERSION {
+			return Err(BinaryError::UnsupportedVersion(version));
+		}
+
+		let name_len = u32::from_le_bytes(reader.take(4)?.try_into().unwrap()) as usize;
+		let name = String::from_utf8(reader.take(name_len)?.to_vec())
+			.map_err(|_| BinaryError::InvalidName)?;
+		let min = Coordinate {
+			x: reader.f64()?,
+			y: reader.f64()?,
+		};
+		let max = Coordinate {
+			x: reader.f64()?,
+			y: reader.f64()?,
+		};
+		let fill_rule = reader.tag()?;
+		let fill_rule = *FillRule::ALL
+			.get(fill_rule as usize)
+			.ok_or(BinaryError::InvalidTag(fill_rule))?;
+		let parts = reader.part(0)?;
+
+		if !reader.bytes.is_empty() {
+			return Err(BinaryError::TrailingBytes);
+		}
+		Ok(Self {
+			name,
+			bounding_rect: Rect::new(min, max),
+			fill_rule,
+			parts,
+		})
+	}
+}
+
+fn write_check(check: &LineSplitCheck, result: &mut Vec<u8>) {
+	let (tag, k, b) = match check {
+		LineSplitCheck::MulToX { k, b } => (0, k, b),
+		LineSplitCheck::MulToY { k, b } => (1, k, b),
+	};
+	result.push(tag);
+	result.extend_from_slice(&k.to_le_bytes());
+	result.extend_from_slice(&b.to_le_bytes());
+}
+
+fn write_generalized(check: &LineSplitCheckGeneralized, result: &mut Vec<u8>) {
+	match check {
+		LineSplitCheckGeneralized::Less(check) => {
+			result.push(0);
+			write_check(check, result);
+		},
+		LineSplitCheckGeneralized::Greater(check) => {
+			result.push(1);
+			write_check(check, result);
+		},
+	}
+}
+
+fn write_part(part: &PolygonFastPrecalculatorPart, result: &mut Vec<u8>) {
+	use PolygonFastPrecalculatorPart::*;
+	match part {
+		None => result.push(0),
+		LineSplit {
+			check,
+			less,
+			greater,
+		} => {
+			result.push(1);
+			write_check(check, result);
+			write_part(less, result);
+			write_part(greater, result);
+		},
+		Triangle { checks } => {
+			result.push(2);
+			for check in checks.iter() {
+				write_generalized(check, result);
+			}
+		},
+		Edges { edges } => {
+			result.push(3);
+			result.extend_from_slice(&(edges.len() as u32).to_le_bytes());
+			for edge in edges {
+				result.extend_from_slice(&edge.y_min.to_le_bytes());
+				result.extend_from_slice(&edge.y_max.to_le_bytes());
+				write_generalized(&edge.check, result);
+			}
+		},
+	}
+}
+
+struct Reader<'a> {
+	bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+	fn take(&mut self, len: usize) -> Result<&'a [u8], BinaryError> {
+		if self.bytes.len() < len {
+			return Err(BinaryError::UnexpectedEnd);
+		}
+		let (result, rest) = self.bytes.split_at(len);
+		self.bytes = rest;
+		Ok(result)
+	}
+
+	fn tag(&mut self) -> Result<u8, BinaryError> {
+		Ok(self.take(1)?[0])
+	}
+
+	fn f64(&mut self) -> Result<f64, BinaryError> {
+		Ok(f64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+	}
+
+	fn check(&mut self) -> Result<LineSplitCheck, BinaryError> {
+		let tag = self.tag()?;
+		let (k, b) = (self.f64()?, self.f64()?);
+		match tag {
+			0 => Ok(LineSplitCheck::MulToX { k, b }),
+			1 => Ok(LineSplitCheck::MulToY { k, b }),
+			_ => Err(BinaryError::InvalidTag(tag)),
+		}
+	}
+
+	fn generalized(&mut self) -> Result<LineSplitCheckGeneralized, BinaryError> {
+		let tag = self.tag()?;
+		let check = self.check()?;
+		match tag {
+			0 => Ok(LineSplitCheckGeneralized::Less(check)),
+			1 => Ok(LineSplitCheckGeneralized::Greater(check)),
+			_ => Err(BinaryError::InvalidTag(tag)),
+		}
+	}
+
+	fn part(&mut self, depth: usize) -> Result<PolygonFastPrecalculatorPart, BinaryError> {
+		if depth > MAX_DEPTH {
+			return Err(BinaryError::TooDeep);
+		}
+		match self.tag()? {
+			0 => Ok(PolygonFastPrecalculatorPart::None),
+			1 => Ok(PolygonFastPrecalculatorPart::LineSplit {
+				check: self.check()?,
+				less: Box::new(self.part(depth + 1)?),
+				greater: Box::new(self.part(depth + 1)?),
+			}),
+			2 => Ok(PolygonFastPrecalculatorPart::Triangle {
+				checks: [
+					self.generalized()?,
+					self.generalized()?,
+					self.generalized()?,
+				],
+			}),
+			3 => {
+				let len = u32::from_le_bytes(self.take(4)?.try_into().unwrap());
+				let edges = (0..len)
+					.map(|_| {
+						Ok(CrossingEdge {
+							y_min: self.f64()?,
+							y_max: self.f64()?,
+							check: self.generalized()?,
+						})
+					})
+					.collect::<Result<_, _>>()?;
+				Ok(PolygonFastPrecalculatorPart::Edges { edges })
+			},
+			tag => Err(BinaryError::InvalidTag(tag)),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::vec_to_multipolygon;
+
+	#[test]
+	fn round_trip() {
+		let tree = PolygonFastPrecalculator::calc(
+			"test".to_owned(),
+			vec_to_multipolygon(vec![(0., 0.), (4., 0.), (4., 3.), (2., 1.), (0., 3.)]),
+		);
+		let bytes = tree.to_bytes();
+		assert_eq!(
+			PolygonFastPrecalculator::from_bytes(&bytes)
+				.unwrap()
+				.to_bytes(),
+			bytes
+		);
+	}
+
+	#[test]
+	fn too_deep() {
+		let mut bytes = PolygonFastPrecalculator::calc(
+			"test".to_owned(),
+			vec_to_multipolygon(vec![(0., 0.), (1., 0.), (0., 1.)]),
+		)
+		.to_bytes();
+		// Parts start after the fill rule, they are replaced with a chain of splits
+		let parts = MAGIC.len() + 2 + 4 + "test".len() + 4 * 8 + 1;
+		bytes.truncate(parts);
+		let check = LineSplitCheck::MulToX { k: 0., b: 0.5 };
+		let chain = |depth: usize| {
+			let mut bytes = bytes.clone();
+			for _ in 0..depth {
+				bytes.push(1);
+				write_check(&check, &mut bytes);
+			}
+			// Both sides of the last split and greater sides of all others
+			bytes.extend(std::iter::repeat(0).take(depth + 1));
+			bytes
+		};
+		assert!(PolygonFastPrecalculator::from_bytes(&chain(MAX_DEPTH)).is_ok());
+		assert_eq!(
+			PolygonFastPrecalculator::from_bytes(&chain(MAX_DEPTH + 10)).unwrap_err(),
+			BinaryError::TooDeep
+		);
+	}
+}
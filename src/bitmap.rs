@@ -0,0 +1,243 @@
+use std::{collections::BTreeMap, fmt, fs::File, path::Path};
+
+use geo::{prelude::Simplify, Coordinate, LineString, MultiPolygon};
+
+use crate::rings_to_multipolygon;
+
+#[derive(Debug)]
+pub enum BitmapError {
+	Io(std::io::Error),
+	Png(png::DecodingError),
+}
+
+impl fmt::Display for BitmapError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			BitmapError::Io(err) => write!(f, "can't open image: {}", err),
+			BitmapError::Png(err) => write!(f, "can't decode png: {}", err),
+		}
+	}
+}
+
+impl std::error::Error for BitmapError {}
+
+/// Reads mask from png file and traces it, see `Mask::to_multipolygon`.
+pub fn from_png_mask<P: AsRef<Path>>(
+	path: P,
+	threshold: u8,
+	tolerance: f64,
+) -> Result<MultiPolygon<f64>, BitmapError> {
+	Ok(Mask::from_png(path)?.to_multipolygon(threshold, tolerance))
+}
+
+/// Grayscale mask, row by row from the top.
+#[derive(Clone, Debug)]
+pub struct Mask {
+	pub width: usize,
+	pub height: usize,
+	pub data: Vec<u8>,
+}
+
+impl Mask {
+	/// Takes alpha channel of the image, or brightness if image has no alpha.
+	pub fn from_png<P: AsRef<Path>>(path: P) -> Result<Self, BitmapError> {
+		let file = File::open(path).map_err(BitmapError::Io)?;
+		let mut decoder = png::Decoder::new(file);
+		decoder.set_transformations(png::Transformations::EXPAND | png::Transformations::STRIP_16);
+		let (info, mut reader) = decoder.read_info().map_err(BitmapError::Png)?;
+		let mut buffer = vec![0; reader.output_buffer_size()];
+		reader.next_frame(&mut buffer).map_err(BitmapError::Png)?;
+
+		let (color_type, _) = reader.output_color_type();
+		let samples = color_type.samples();
+		let (width, height) = (info.width as usize, info.height as usize);
+		let line_size = buffer.len() / height;
+
+		let mut data = Vec::with_capacity(width * height);
+		for y in 0..height {
+			for x in 0..width {
+				let pixel = &buffer[y * line_size + x * samples..][..samples];
+				data.push(match color_type {
+					png::ColorType::GrayscaleAlpha => pixel[1],
+					png::ColorType::RGBA => pixel[3],
+					png::ColorType::RGB => {
+						((pixel[0] as u32 + pixel[1] as u32 + pixel[2] as u32) / 3) as u8
+					},
+					png::ColorType::Grayscale | png::ColorType::Indexed => pixel[0],
+				});
+			}
+		}
+
+		Ok(Self {
+			width,
+			height,
+			data,
+		})
+	}
+
+	fn get(&self, x: isize, y: isize) -> f64 {
+		if x < 0 || y < 0 || x >= self.width as isize || y >= self.height as isize {
+			0.
+		} else {
+			self.data[y as usize * self.width + x as usize] as f64
+		}
+	}
+
+	/// Traces contours of pixels with value not less than `threshold` by marching squares, and simplifies them with `tolerance` in pixels. One pixel is one unit, y axis points up, so shape doesn't look flipped in the shader.
+	pub fn to_multipolygon(&self, threshold: u8, tolerance: f64) -> MultiPolygon<f64> {
+		// Crossing of the contour with the edge between two neighbour pixel centers: `(x, y, is_horizontal)` of the edge start
+		type Key = (isize, isize, bool);
+
+		let threshold = threshold as f64;
+		let inside = |x: isize, y: isize| self.get(x, y) >= threshold;
+		let crossing = |key: Key| {
+			let (x, y, horizontal) = key;
+			let (x2, y2) = if horizontal { (x + 1, y) } else { (x, y + 1) };
+			let (a, b) = (self.get(x, y), self.get(x2, y2));
+			let t = if a == b {
+				0.5
+			} else {
+				((threshold - a) / (b - a)).max(0.).min(1.)
+			};
+			Coordinate {
+				x: x as f64 + (x2 - x) as f64 * t + 0.5,
+				y: self.height as f64 - (y as f64 + (y2 - y) as f64 * t + 0.5),
+			}
+		};
+
+		// Corners of each cell are walked around, and the crossing where a run of inside corners ends is joined with the crossing where the next run starts, so saddles join diagonal pixels and every crossing starts exactly one segment
+		let mut next: BTreeMap<Key, Key> = BTreeMap::new();
+		for y in -1..self.height as isize {
+			for x in -1..self.width as isize {
+				let corners = [(x, y), (x + 1, y), (x + 1, y + 1), (x, y + 1)];
+				let edges = [
+					(x, y, true),
+					(x + 1, y, false),
+					(x, y + 1, true),
+					(x, y, false),
+				];
+				let is_inside = [
+					inside(corners[0].0, corners[0].1),
+					inside(corners[1].0, corners[1].1),
+					inside(corners[2].0, corners[2].1),
+					inside(corners[3].0, corners[3].1),
+				];
+
+				let mut run_start = None;
+				let mut pending_end = None;
+				for i in 0..4 {
+					let j = (i + 1) % 4;
+					if is_inside[i] == is_inside[j] {
+						continue;
+					}
+					if is_inside[j] {
+						match pending_end.take() {
+							Some(end) => {
+								next.insert(end, edges[i]);
+							},
+							None => run_start = Some(edges[i]),
+						}
+					} else {
+						pending_end = Some(edges[i]);
+					}
+				}
+				// Run that wraps around the first corner
+				if let (Some(end), Some(start)) = (pending_end, run_start) {
+					next.insert(end, start);
+				}
+			}
+		}
+
+		let mut rings = Vec::new();
+		// Map is ordered, so rings start from the same crossing on every run
+		while let Some(&first) = next.keys().next() {
+			let mut ring = Vec::new();
+			let mut current = first;
+			while let Some(following) = next.remove(&current) {
+				ring.push(crossing(current));
+				current = following;
+			}
+			if ring.len() >= 3 {
+				ring.push(ring[0]);
+				rings.push(LineString(ring).simplify(&tolerance));
+			}
+		}
+
+		rings_to_multipolygon(rings)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use geo::Point;
+
+	use super::Mask;
+	use crate::fill_rule::{ring_signed_area, ring_winding_number, FillRule};
+
+	// Square 2..14 with hole 6..10, and two pixels touching by corner at (16, 2) and (17, 3)
+	fn mask() -> Mask {
+		let (width, height) = (20, 16);
+		let mut data = vec![0; width * height];
+		for y in 2..14 {
+			for x in 2..14 {
+				if !(6..10).contains(&x) || !(6..10).contains(&y) {
+					data[y * width + x] = 255;
+				}
+			}
+		}
+		data[2 * width + 16] = 255;
+		data[3 * width + 17] = 255;
+		Mask {
+			width,
+			height,
+			data,
+		}
+	}
+
+	#[test]
+	fn square_with_hole_and_diagonal() {
+		let exact = mask().to_multipolygon(128, 0.);
+		let area = |ring| ring_signed_area(ring).abs();
+		// Contour goes almost through the middle between pixel centers and cuts corners by half a pixel
+		assert!((area(exact.0[0].exterior()) - (12. * 12. - 0.5)).abs() < 0.2);
+		assert!((area(&exact.0[0].interiors()[0]) - (4. * 4. - 0.5)).abs() < 0.2);
+		// Saddle joins diagonal pixels into one ring
+		assert!((area(exact.0[1].exterior()) - (2. - 0.5)).abs() < 0.2);
+
+		for &tolerance in &[0., 0.5] {
+			let polygon = mask().to_multipolygon(128, tolerance);
+			assert_eq!(polygon.0.len(), 2);
+			let square = &polygon.0[0];
+			let diagonal = &polygon.0[1];
+			assert_eq!(square.interiors().len(), 1);
+			assert!(diagonal.interiors().is_empty());
+
+			// Exteriors go the same way, the hole goes the opposite one
+			let sign = ring_signed_area(square.exterior()).signum();
+			assert_eq!(ring_signed_area(diagonal.exterior()).signum(), sign);
+			assert_eq!(ring_signed_area(&square.interiors()[0]).signum(), -sign);
+
+			// Hole lies inside of its exterior, y axis points up
+			for point in square.interiors()[0].points_iter() {
+				assert_ne!(ring_winding_number(square.exterior(), point), 0);
+			}
+			for &(x, y, inside) in &[
+				(3., 13., true),
+				(8., 8., false),
+				(16.5, 13.5, true),
+				(17.5, 12.5, true),
+				(17.5, 13.5, false),
+				(1., 1., false),
+			] {
+				assert_eq!(
+					FillRule::NonZero.contains(&polygon, Point::new(x, y)),
+					inside,
+					"{} {} with tolerance {}",
+					x,
+					y,
+					tolerance
+				);
+			}
+		}
+	}
+}
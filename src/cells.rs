@@ -0,0 +1,307 @@
+use geo::{Coordinate, Line, LineString, Point, Polygon};
+
+use crate::{
+	LineSplitCheck, LineSplitCheckGeneralized, PolygonFastPrecalculator,
+	PolygonFastPrecalculatorPart,
+};
+
+/// Part of the tree with the convex region of plane where it is evaluated.
+#[derive(Clone, Debug)]
+pub struct Cell<'a> {
+	pub part: &'a PolygonFastPrecalculatorPart,
+	pub depth: usize,
+	/// In coordinates of the original polygon.
+	pub polygon: Polygon<f64>,
+	/// In `[0, 1]²` where the tree works.
+	pub normalized: Vec<Coordinate<f64>>,
+}
+
+impl PolygonFastPrecalculator {
+	/// All parts of the tree with their cells, parent goes before children.
+	pub fn cells(&self) -> Vec<Cell<'_>> {
+		fn cells_inner<'a>(
+			part: &'a PolygonFastPrecalculatorPart,
+			depth: usize,
+			cell: Vec<Coordinate<f64>>,
+			to_original: &impl Fn(Coordinate<f64>) -> Coordinate<f64>,
+			result: &mut Vec<Cell<'a>>,
+		) {
+			if let PolygonFastPrecalculatorPart::LineSplit {
+				check,
+				less,
+				greater,
+			} = part
+			{
+				let less_cell = clip_convex(&cell, &LineSplitCheckGeneralized::Less(*check));
+				let greater_cell = clip_convex(&cell, &LineSplitCheckGeneralized::Greater(*check));
+				result.push(Cell {
+					part,
+					depth,
+					polygon: to_polygon(&cell, to_original),
+					normalized: cell,
+				});
+				cells_inner(less, depth + 1, less_cell, to_original, result);
+				cells_inner(greater, depth + 1, greater_cell, to_original, result);
+			} else {
+				result.push(Cell {
+					part,
+					depth,
+					polygon: to_polygon(&cell, to_original),
+					normalized: cell,
+				});
+			}
+		}
+
+		let to_original = |coord: Coordinate<f64>| self.to_original(coord);
+		let square = vec![
+			Coordinate { x: 0., y: 0. },
+			Coordinate { x: 1., y: 0. },
+			Coordinate { x: 1., y: 1. },
+			Coordinate { x: 0., y: 1. },
+		];
+		let mut result = Vec::new();
+		cells_inner(&self.parts, 0, square, &to_original, &mut result);
+		result
+	}
+
+	/// Converts point from `[0, 1]²` where the tree works to coordinates of the original polygon.
+	pub fn to_original(&self, coord: Coordinate<f64>) -> Coordinate<f64> {
+		let min = self.bounding_rect.min();
+		Coordinate {
+			x: min.x + coord.x * self.bounding_rect.width(),
+			y: min.y + coord.y * self.bounding_rect.height(),
+		}
+	}
+}
+
+impl Cell<'_> {
+	/// Split line of `LineSplit` part, clipped to the cell.
+	pub fn split_line(&self, precalculator: &PolygonFastPrecalculator) -> Option<Line<f64>> {
+		match self.part {
+			PolygonFastPrecalculatorPart::LineSplit { check, .. } => {
+				line_in_convex(&self.normalized, check).map(|line| {
+					Line::new(
+						precalculator.to_original(line.start),
+						precalculator.to_original(line.end),
+					)
+				})
+			},
+			_ => None,
+		}
+	}
+
+	/// Triangle of `Triangle` part, clipped to the cell.
+	pub fn triangle(&self, precalculator: &PolygonFastPrecalculator) -> Option<Polygon<f64>> {
+		match self.part {
+			PolygonFastPrecalculatorPart::Triangle { checks } => {
+				let mut cell = self.normalized.clone();
+				for check in checks.iter() {
+					cell = clip_convex(&cell, check);
+				}
+				Some(to_polygon(&cell, &|coord| precalculator.to_original(coord)))
+			},
+			_ => None,
+		}
+	}
+
+	/// Edges of `Edges` part.
+	pub fn edges(&self, precalculator: &PolygonFastPrecalculator) -> Vec<Line<f64>> {
+		match self.part {
+			PolygonFastPrecalculatorPart::Edges { edges } => edges
+				.iter()
+				.map(|edge| {
+					let check = match edge.check {
+						LineSplitCheckGeneralized::Less(check)
+						| LineSplitCheckGeneralized::Greater(check) => check,
+					};
+					let at = |y: f64| {
+						// Edges are never horizontal, so `k` of `MulToX` is not zero
+						let x = match check {
+							LineSplitCheck::MulToY { k, b } => y * k + b,
+							LineSplitCheck::MulToX { k, b } => (y - b) / k,
+						};
+						precalculator.to_original(Coordinate { x, y })
+					};
+					Line::new(at(edge.y_min), at(edge.y_max))
+				})
+				.collect(),
+			_ => Vec::new(),
+		}
+	}
+}
+
+fn to_polygon(
+	cell: &[Coordinate<f64>],
+	to_original: &impl Fn(Coordinate<f64>) -> Coordinate<f64>,
+) -> Polygon<f64> {
+	Polygon::new(
+		LineString(cell.iter().map(|coord| to_original(*coord)).collect()),
+		vec![],
+	)
+}
+
+fn value(check: &LineSplitCheck, coord: Coordinate<f64>) -> f64 {
+	check.less_count(Point(coord)).1
+}
+
+/// Part of the convex polygon where `check` is true, by Sutherland–Hodgman algorithm.
+pub fn clip_convex(
+	polygon: &[Coordinate<f64>],
+	check: &LineSplitCheckGeneralized,
+) -> Vec<Coordinate<f64>> {
+	let (line, sign) = match check {
+		LineSplitCheckGeneralized::Less(line) => (line, -1.),
+		LineSplitCheckGeneralized::Greater(line) => (line, 1.),
+	};
+	let inside = |coord: Coordinate<f64>| value(line, coord) * sign >= 0.;
+
+	let mut result = Vec::new();
+	for (i, current) in polygon.iter().enumerate() {
+		let next = polygon[(i + 1) % polygon.len()];
+		if inside(*current) {
+			result.push(*current);
+		}
+		if inside(*current) != inside(next) {
+			result.push(intersection(line, *current, next));
+		}
+	}
+	result
+}
+
+fn intersection(check: &LineSplitCheck, a: Coordinate<f64>, b: Coordinate<f64>) -> Coordinate<f64> {
+	let (va, vb) = (value(check, a), value(check, b));
+	let t = va / (va - vb);
+	Coordinate {
+		x: a.x + (b.x - a.x) * t,
+		y: a.y + (b.y - a.y) * t,
+	}
+}
+
+fn line_in_convex(polygon: &[Coordinate<f64>], check: &LineSplitCheck) -> Option<Line<f64>> {
+	let mut points = Vec::new();
+	for (i, current) in polygon.iter().enumerate() {
+		let next = polygon[(i + 1) % polygon.len()];
+		if (value(check, *current) < 0.) != (value(check, next) < 0.) {
+			points.push(intersection(check, *current, next));
+		}
+	}
+	if points.len() >= 2 {
+		Some(Line::new(points[0], points[1]))
+	} else {
+		None
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use geo::{LineString, MultiPolygon, Point, Polygon};
+
+	use crate::{
+		edges, fill_rule::distance_to_segment, CalcOptions, FillRule, PolygonFastPrecalculator,
+	};
+
+	fn polygon(exterior: &[(f64, f64)], holes: &[&[(f64, f64)]]) -> MultiPolygon<f64> {
+		MultiPolygon(vec![Polygon::new(
+			LineString::from(exterior.to_vec()),
+			holes
+				.iter()
+				.map(|hole| LineString::from(hole.to_vec()))
+				.collect(),
+		)])
+	}
+
+	// Points on every split line and just beside it, both leaves of a seam use the same line, so there are no pinholes and no double coverage
+	fn check_seams(polygon: &MultiPolygon<f64>, options: &CalcOptions) {
+		let tree = PolygonFastPrecalculator::calc_with_options(
+			"seams".to_owned(),
+			polygon.clone(),
+			options,
+		);
+		let boundary = edges(polygon).collect::<Vec<_>>();
+		let rect = tree.bounding_rect;
+		let size = rect.width().max(rect.height());
+		for cell in tree.cells() {
+			let line = match cell.split_line(&tree) {
+				Some(line) => line,
+				None => continue,
+			};
+			let length = (line.dx() * line.dx() + line.dy() * line.dy()).sqrt();
+			if length == 0. {
+				continue;
+			}
+			let (nx, ny) = (-line.dy() / length, line.dx() / length);
+			for i in 1..100 {
+				let t = i as f64 / 100.;
+				let (x, y) = (line.start.x + line.dx() * t, line.start.y + line.dy() * t);
+				for &offset in &[0., 1e-9 * size, -1e-9 * size] {
+					let point = Point::new(x + nx * offset, y + ny * offset);
+					// Inside of the boundary itself is not defined
+					let distance = boundary
+						.iter()
+						.map(|edge| distance_to_segment(edge, point.0))
+						.fold(f64::INFINITY, f64::min);
+					if distance < 1e-6 * size {
+						continue;
+					}
+					assert_eq!(
+						tree.is_inside(point),
+						options.fill_rule.contains(polygon, point),
+						"{:?} on {:?}",
+						point,
+						line
+					);
+				}
+			}
+		}
+	}
+
+	#[test]
+	fn seams() {
+		let comb = polygon(
+			&[
+				(0., 0.),
+				(5., 0.),
+				(5., 3.),
+				(4., 3.),
+				(4., 1.),
+				(3., 1.),
+				(3., 3.),
+				(2., 3.),
+				(2., 1.),
+				(1., 1.),
+				(1., 3.),
+				(0., 3.),
+			],
+			&[],
+		);
+		let square_with_hole = polygon(&[(0., 0.), (4., 0.), (4., 4.), (0., 4.)], &[&[
+			(1., 1.),
+			(1., 3.),
+			(3., 3.),
+			(3., 1.),
+		]]);
+		let pentagram = polygon(
+			&[
+				(0., 1.),
+				(-0.5878, -0.809),
+				(0.9511, 0.309),
+				(-0.9511, 0.309),
+				(0.5878, -0.809),
+			],
+			&[],
+		);
+
+		for polygon in &[comb, square_with_hole, pentagram.clone()] {
+			check_seams(polygon, &CalcOptions::default());
+			// Edges of `Edges` leaves lie on the split lines too
+			check_seams(polygon, &CalcOptions {
+				max_edges_per_leaf: 8,
+				..CalcOptions::default()
+			});
+		}
+		check_seams(&pentagram, &CalcOptions {
+			fill_rule: FillRule::EvenOdd,
+			..CalcOptions::default()
+		});
+	}
+}
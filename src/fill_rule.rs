@@ -0,0 +1,374 @@
+use geo::{Coordinate, Line, LineString, MultiPolygon, Point, Polygon};
+use line_intersection::LineInterval;
+#[cfg(feature = "serialize")]
+use serde::{Deserialize, Serialize};
+
+/// Rule that decides which regions of a self-intersecting polygon are inside, same as in SVG, Clipper, etc.
+#[derive(Clone, Debug, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub enum FillRule {
+	EvenOdd,
+	NonZero,
+	Positive,
+	Negative,
+}
+
+impl Default for FillRule {
+	// Clipper used this rule internally before fill rule was selectable
+	fn default() -> Self {
+		FillRule::NonZero
+	}
+}
+
+impl FillRule {
+	pub const ALL: [FillRule; 4] = [
+		FillRule::EvenOdd,
+		FillRule::NonZero,
+		FillRule::Positive,
+		FillRule::Negative,
+	];
+
+	pub fn name(&self) -> &'static str {
+		use FillRule::*;
+		match self {
+			EvenOdd => "even-odd",
+			NonZero => "nonzero",
+			Positive => "positive",
+			Negative => "negative",
+		}
+	}
+
+	pub fn is_filled(&self, winding: i32) -> bool {
+		use FillRule::*;
+		match self {
+			EvenOdd => winding % 2 != 0,
+			NonZero => winding != 0,
+			Positive => winding > 0,
+			Negative => winding < 0,
+		}
+	}
+
+	/// CPU reference check: is point inside polygon according to this rule.
+	pub fn contains(&self, polygon: &MultiPolygon<f64>, point: Point<f64>) -> bool {
+		self.is_filled(winding_number(polygon, point))
+	}
+
+	/// Converts polygon to non-self-intersecting polygons with holes, whose inside is exactly the inside of `polygon` by this rule.
+	pub fn resolve(&self, polygon: &MultiPolygon<f64>) -> MultiPolygon<f64> {
+		let rings = oriented_rings(polygon);
+		let is_inside = |point: Point<f64>| {
+			self.is_filled(
+				rings
+					.iter()
+					.map(|ring| ring_winding_number(ring, point))
+					.sum(),
+			)
+		};
+		let segments = rings
+			.iter()
+			.map(|ring| ring.lines())
+			.flatten()
+			.filter(|line| line.dx() != 0. || line.dy() != 0.)
+			.collect::<Vec<_>>();
+
+		let scale = segments
+			.iter()
+			.map(|line| line.dx().abs().max(line.dy().abs()))
+			.fold(0., f64::max);
+		if scale == 0. {
+			return MultiPolygon(vec![]);
+		}
+		let eps = scale * 1e-9;
+
+		// Split every segment in all intersection points, so edges intersects only by vertices
+		let mut vertices: Vec<Coordinate<f64>> = Vec::new();
+		let mut vertex_index = |coord: Coordinate<f64>| -> usize {
+			if let Some(pos) = vertices
+				.iter()
+				.position(|v| (v.x - coord.x).abs() < eps && (v.y - coord.y).abs() < eps)
+			{
+				pos
+			} else {
+				vertices.push(coord);
+				vertices.len() - 1
+			}
+		};
+
+		let mut edges: Vec<(usize, usize)> = Vec::new();
+		for (i, a) in segments.iter().enumerate() {
+			let mut params = vec![0., 1.];
+			for (j, b) in segments.iter().enumerate() {
+				if i == j {
+					continue;
+				}
+				if let Some(point) = LineInterval::line_segment(*a)
+					.relate(&LineInterval::line_segment(*b))
+					.unique_intersection()
+				{
+					params.push(line_param(a, point.into()));
+				}
+				// Collinear overlapping segments: split by ends of other segment
+				for end in &[b.start, b.end] {
+					if distance_to_segment(a, *end) < eps {
+						params.push(line_param(a, *end));
+					}
+				}
+			}
+			params.retain(|t| (0. ..=1.).contains(t));
+			params.sort_by(|x, y| x.partial_cmp(y).unwrap());
+
+			let at = |t: f64| Coordinate {
+				x: a.start.x + a.dx() * t,
+				y: a.start.y + a.dy() * t,
+			};
+			for (t1, t2) in params.iter().zip(params.iter().skip(1)) {
+				let start = vertex_index(at(*t1));
+				let end = vertex_index(at(*t2));
+				if start != end {
+					edges.push((start, end));
+				}
+			}
+		}
+
+		// Keep only edges that separate inside from outside, oriented so inside is on the left
+		edges.iter_mut().for_each(|edge| {
+			if edge.0 > edge.1 {
+				*edge = (edge.1, edge.0);
+			}
+		});
+		edges.sort();
+		edges.dedup();
+		let mut boundary = Vec::new();
+		for (start, end) in edges {
+			let (a, b) = (vertices[start], vertices[end]);
+			let (dx, dy) = (b.x - a.x, b.y - a.y);
+			let len = (dx * dx + dy * dy).sqrt();
+			let offset = scale * 1e-7;
+			let (nx, ny) = (-dy / len * offset, dx / len * offset);
+			let (mx, my) = ((a.x + b.x) / 2., (a.y + b.y) / 2.);
+			let left = is_inside(Point::new(mx + nx, my + ny));
+			let right = is_inside(Point::new(mx - nx, my - ny));
+			if left && !right {
+				boundary.push((start, end));
+			} else if right && !left {
+				boundary.push((end, start));
+			}
+		}
+
+		// Link edges into rings, on each vertex take the first edge clockwise from the incoming one, so rings touching by vertex are separated
+		let angle = |from: usize, to: usize| {
+			let (a, b) = (vertices[from], vertices[to]);
+			(b.y - a.y).atan2(b.x - a.x)
+		};
+		let mut used = vec![false; boundary.len()];
+		let mut exteriors = Vec::new();
+		let mut holes = Vec::new();
+		for first in 0..boundary.len() {
+			if used[first] {
+				continue;
+			}
+			used[first] = true;
+			let mut ring = vec![boundary[first].0];
+			let mut current = first;
+			while boundary[current].1 != boundary[first].0 {
+				let (from, at) = boundary[current];
+				let back = angle(at, from);
+				let next = boundary
+					.iter()
+					.enumerate()
+					.filter(|(i, (start, _))| !used[*i] && *start == at)
+					.map(|(i, (_, end))| {
+						let mut turn = back - angle(at, *end);
+						while turn <= 0. {
+							turn += 2. * std::f64::consts::PI;
+						}
+						(i, turn)
+					})
+					.min_by(|(_, x), (_, y)| x.partial_cmp(y).unwrap());
+				match next {
+					Some((i, _)) => {
+						used[i] = true;
+						ring.push(at);
+						current = i;
+					},
+					// Broken ring because of numerical errors
+					None => break,
+				}
+			}
+			ring.push(boundary[current].1);
+
+			let ring = LineString(ring.into_iter().map(|i| vertices[i]).collect());
+			let area = ring_signed_area(&ring);
+			if area.abs() < eps * scale {
+				continue;
+			}
+			if area > 0. {
+				exteriors.push(ring);
+			} else {
+				holes.push(ring);
+			}
+		}
+
+		let mut result = exteriors
+			.into_iter()
+			.map(|exterior| Polygon::new(exterior, vec![]))
+			.collect::<Vec<_>>();
+		for hole in holes {
+			// Point near the hole on the filled side, it lies inside parent exterior
+			let Line { start: a, end: b } = hole.lines().next().unwrap();
+			let (dx, dy) = (b.x - a.x, b.y - a.y);
+			let len = (dx * dx + dy * dy).sqrt();
+			let offset = scale * 1e-7;
+			let point = Point::new(
+				(a.x + b.x) / 2. - dy / len * offset,
+				(a.y + b.y) / 2. + dx / len * offset,
+			);
+			let parent = result
+				.iter_mut()
+				.filter(|poly| ring_winding_number(poly.exterior(), point) != 0)
+				.min_by(|x, y| {
+					let x = ring_signed_area(x.exterior());
+					let y = ring_signed_area(y.exterior());
+					x.partial_cmp(&y).unwrap()
+				});
+			if let Some(parent) = parent {
+				parent.interiors_push(hole);
+			}
+		}
+
+		MultiPolygon(result)
+	}
+}
+
+/// Sum of winding numbers of all rings. Holes are counted with orientation opposite to their exterior, whatever orientation they were written with.
+pub fn winding_number(polygon: &MultiPolygon<f64>, point: Point<f64>) -> i32 {
+	oriented_rings(polygon)
+		.iter()
+		.map(|ring| ring_winding_number(ring, point))
+		.sum()
+}
+
+pub fn ring_winding_number(ring: &LineString<f64>, point: Point<f64>) -> i32 {
+	let (x, y) = (point.x(), point.y());
+	let mut result = 0;
+	for line in ring.lines() {
+		let (a, b) = (line.start, line.end);
+		let cross = (b.x - a.x) * (y - a.y) - (x - a.x) * (b.y - a.y);
+		if a.y <= y {
+			if b.y > y && cross > 0. {
+				result += 1;
+			}
+		} else if b.y <= y && cross < 0. {
+			result -= 1;
+		}
+	}
+	result
+}
+
+pub fn ring_signed_area(ring: &LineString<f64>) -> f64 {
+	ring.lines()
+		.map(|line| line.start.x * line.end.y - line.end.x * line.start.y)
+		.sum::<f64>()
+		/ 2.
+}
+
+pub(crate) fn oriented_rings(polygon: &MultiPolygon<f64>) -> Vec<LineString<f64>> {
+	let mut result = Vec::new();
+	for poly in polygon.0.iter() {
+		let sign = ring_signed_area(poly.exterior()).signum();
+		result.push(poly.exterior().clone());
+		for interior in poly.interiors() {
+			let mut interior = interior.clone();
+			if ring_signed_area(&interior).signum() == sign {
+				interior.0.reverse();
+			}
+			result.push(interior);
+		}
+	}
+	result
+}
+
+fn line_param(line: &Line<f64>, point: Coordinate<f64>) -> f64 {
+	let (dx, dy) = (line.dx(), line.dy());
+	((point.x - line.start.x) * dx + (point.y - line.start.y) * dy) / (dx * dx + dy * dy)
+}
+
+pub(crate) fn distance_to_segment(line: &Line<f64>, point: Coordinate<f64>) -> f64 {
+	let t = line_param(line, point).max(0.).min(1.);
+	let (x, y) = (line.start.x + line.dx() * t, line.start.y + line.dy() * t);
+	((point.x - x).powi(2) + (point.y - y).powi(2)).sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+	use geo::{LineString, MultiPolygon, Point, Polygon};
+
+	use super::FillRule;
+	use crate::{CalcOptions, PolygonFastPrecalculator};
+
+	// Every second vertex of a regular pentagon, so the center is wound twice
+	fn pentagram(clockwise: bool) -> MultiPolygon<f64> {
+		let mut coords = (0..5)
+			.map(|i| {
+				let angle = std::f64::consts::FRAC_PI_2 + i as f64 * 4. * std::f64::consts::PI / 5.;
+				(angle.cos(), angle.sin())
+			})
+			.collect::<Vec<_>>();
+		if clockwise {
+			coords.reverse();
+		}
+		MultiPolygon(vec![Polygon::new(LineString::from(coords), vec![])])
+	}
+
+	#[test]
+	fn pentagram_by_fill_rule() {
+		use FillRule::*;
+
+		let center = Point::new(0., 0.);
+		let tip = Point::new(0., 0.8);
+		let outside = Point::new(0.9, 0.9);
+		// Clockwise, rule, is center inside, is tip inside
+		let cases = [
+			(false, EvenOdd, false, true),
+			(false, NonZero, true, true),
+			(false, Positive, true, true),
+			(false, Negative, false, false),
+			(true, EvenOdd, false, true),
+			(true, NonZero, true, true),
+			(true, Positive, false, false),
+			(true, Negative, true, true),
+		];
+		for &(clockwise, rule, center_inside, tip_inside) in &cases {
+			let polygon = pentagram(clockwise);
+			let options = CalcOptions {
+				fill_rule: rule,
+				..CalcOptions::default()
+			};
+			let tree = PolygonFastPrecalculator::calc_with_options(
+				"pentagram".to_owned(),
+				polygon.clone(),
+				&options,
+			);
+			for &(point, expected) in
+				&[(center, center_inside), (tip, tip_inside), (outside, false)]
+			{
+				assert_eq!(
+					rule.contains(&polygon, point),
+					expected,
+					"contains, {}, clockwise: {}, {:?}",
+					rule.name(),
+					clockwise,
+					point
+				);
+				assert_eq!(
+					tree.is_inside(point),
+					expected,
+					"is_inside, {}, clockwise: {}, {:?}",
+					rule.name(),
+					clockwise,
+					point
+				);
+			}
+		}
+	}
+}
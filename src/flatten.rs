@@ -0,0 +1,54 @@
+// Count of segments so distance between curve and polyline is less than tolerance, `second_difference` is the maximal length of the second difference of control points
+fn segments_count(second_difference: f64, factor: f64, tolerance: f64) -> usize {
+	let count = (second_difference * factor / tolerance).sqrt().ceil();
+	if count.is_finite() {
+		(count as usize).max(1).min(1000)
+	} else {
+		1
+	}
+}
+
+fn length(x: f64, y: f64) -> f64 {
+	(x * x + y * y).sqrt()
+}
+
+pub(crate) fn flatten_quadratic(
+	p0: (f64, f64),
+	p1: (f64, f64),
+	p2: (f64, f64),
+	tolerance: f64,
+	ring: &mut Vec<(f64, f64)>,
+) {
+	let dd = length(p0.0 - 2. * p1.0 + p2.0, p0.1 - 2. * p1.1 + p2.1);
+	let count = segments_count(dd, 1. / 4., tolerance);
+	for i in 1..=count {
+		let t = i as f64 / count as f64;
+		let s = 1. - t;
+		ring.push((
+			s * s * p0.0 + 2. * s * t * p1.0 + t * t * p2.0,
+			s * s * p0.1 + 2. * s * t * p1.1 + t * t * p2.1,
+		));
+	}
+}
+
+pub(crate) fn flatten_cubic(
+	p0: (f64, f64),
+	p1: (f64, f64),
+	p2: (f64, f64),
+	p3: (f64, f64),
+	tolerance: f64,
+	ring: &mut Vec<(f64, f64)>,
+) {
+	let dd1 = length(p0.0 - 2. * p1.0 + p2.0, p0.1 - 2. * p1.1 + p2.1);
+	let dd2 = length(p1.0 - 2. * p2.0 + p3.0, p1.1 - 2. * p2.1 + p3.1);
+	let count = segments_count(dd1.max(dd2), 3. / 4., tolerance);
+	for i in 1..=count {
+		let t = i as f64 / count as f64;
+		let s = 1. - t;
+		let (a, b, c, d) = (s * s * s, 3. * s * s * t, 3. * s * t * t, t * t * t);
+		ring.push((
+			a * p0.0 + b * p1.0 + c * p2.0 + d * p3.0,
+			a * p0.1 + b * p1.1 + c * p2.1 + d * p3.1,
+		));
+	}
+}
@@ -0,0 +1,280 @@
+use std::{fmt, fs, path::Path};
+
+use geo::{Coordinate, LineString, MultiPolygon, Polygon};
+use ttf_parser::{Face, FaceParsingError, OutlineBuilder};
+
+use crate::{
+	fill_rule::FillRule,
+	flatten::{flatten_cubic, flatten_quadratic},
+	CalcOptions, PolygonFastPrecalculator,
+};
+
+#[derive(Debug)]
+pub enum FontError {
+	Io(std::io::Error),
+	Parse(FaceParsingError),
+	MissingGlyph(char),
+	EmptyGlyph(char),
+}
+
+impl fmt::Display for FontError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		use FontError::*;
+		match self {
+			Io(err) => write!(f, "can't read font file: {}", err),
+			Parse(err) => write!(f, "can't parse font: {}", err),
+			MissingGlyph(c) => write!(f, "font has no glyph for {:?}", c),
+			EmptyGlyph(c) => write!(f, "glyph for {:?} has no outline", c),
+		}
+	}
+}
+
+impl std::error::Error for FontError {}
+
+/// Contours of the glyph of `c` in font units, y axis points up, every contour is a separate polygon with its direction kept, so they can be combined with any fill rule. Curves are flattened so they deviate from the original by at most `tolerance` of the em size, so it doesn't depend on units per em of the font.
+pub fn glyph_contours(
+	font: &[u8],
+	c: char,
+	tolerance: f64,
+) -> Result<MultiPolygon<f64>, FontError> {
+	let face = Face::from_slice(font, 0).map_err(FontError::Parse)?;
+	let glyph = face.glyph_index(c).ok_or(FontError::MissingGlyph(c))?;
+	// Units per em out of the valid range are replaced with the common value
+	let units_per_em = face.units_per_em().unwrap_or(1000) as f64;
+
+	let mut builder = Builder::new(tolerance * units_per_em);
+	face.outline_glyph(glyph, &mut builder)
+		.ok_or(FontError::EmptyGlyph(c))?;
+	builder.contours(c)
+}
+
+/// Outline of the glyph of `c`, see `glyph_contours`. Contours are combined by their direction with nonzero rule, as fonts are rendered, so both TrueType and CFF orientations give correct holes.
+pub fn from_glyph(font: &[u8], c: char, tolerance: f64) -> Result<MultiPolygon<f64>, FontError> {
+	let result = FillRule::NonZero.resolve(&glyph_contours(font, c, tolerance)?);
+	if result.0.is_empty() {
+		return Err(FontError::EmptyGlyph(c));
+	}
+	Ok(result)
+}
+
+pub fn from_font_file<P: AsRef<Path>>(
+	path: P,
+	c: char,
+	tolerance: f64,
+) -> Result<MultiPolygon<f64>, FontError> {
+	let font = fs::read(path).map_err(FontError::Io)?;
+	from_glyph(&font, c, tolerance)
+}
+
+/// Name of function for glyph: `glyph_A` for ASCII letters and digits, `glyph_u0416` for others.
+pub fn glyph_name(c: char) -> String {
+	if c.is_ascii_alphanumeric() {
+		format!("glyph_{}", c)
+	} else {
+		format!("glyph_u{:04X}", c as u32)
+	}
+}
+
+impl PolygonFastPrecalculator {
+	/// Calculates `is_inside_glyph_<c>` for the glyph of `c`, see `glyph_contours`. Contours are combined with the fill rule of `options`, fonts are rendered with `FillRule::NonZero`.
+	pub fn calc_glyph(
+		font: &[u8],
+		c: char,
+		tolerance: f64,
+		options: &CalcOptions,
+	) -> Result<Self, FontError> {
+		Ok(Self::calc_with_options(
+			glyph_name(c),
+			glyph_contours(font, c, tolerance)?,
+			options,
+		))
+	}
+}
+
+struct Builder {
+	tolerance: f64,
+	rings: Vec<LineString<f64>>,
+	ring: Vec<(f64, f64)>,
+}
+
+impl Builder {
+	fn new(tolerance: f64) -> Self {
+		Self {
+			tolerance,
+			rings: Vec::new(),
+			ring: Vec::new(),
+		}
+	}
+
+	fn contours(mut self, c: char) -> Result<MultiPolygon<f64>, FontError> {
+		self.finish();
+		if self.rings.is_empty() {
+			return Err(FontError::EmptyGlyph(c));
+		}
+		Ok(MultiPolygon(
+			self.rings
+				.into_iter()
+				.map(|ring| Polygon::new(ring, vec![]))
+				.collect(),
+		))
+	}
+
+	fn current(&self) -> (f64, f64) {
+		self.ring.last().copied().unwrap_or((0., 0.))
+	}
+
+	fn finish(&mut self) {
+		if self.ring.len() >= 3 {
+			self.rings.push(LineString(
+				self.ring
+					.iter()
+					.map(|&(x, y)| Coordinate { x, y })
+					.collect(),
+			));
+		}
+		self.ring.clear();
+	}
+}
+
+impl OutlineBuilder for Builder {
+	fn move_to(&mut self, x: f32, y: f32) {
+		self.finish();
+		self.ring.push((x as f64, y as f64));
+	}
+
+	fn line_to(&mut self, x: f32, y: f32) {
+		self.ring.push((x as f64, y as f64));
+	}
+
+	fn quad_to(&mut self, x1: f32, y1: f32, x: f32, y: f32) {
+		let current = self.current();
+		flatten_quadratic(
+			current,
+			(x1 as f64, y1 as f64),
+			(x as f64, y as f64),
+			self.tolerance,
+			&mut self.ring,
+		);
+	}
+
+	fn curve_to(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, x: f32, y: f32) {
+		let current = self.current();
+		flatten_cubic(
+			current,
+			(x1 as f64, y1 as f64),
+			(x2 as f64, y2 as f64),
+			(x as f64, y as f64),
+			self.tolerance,
+			&mut self.ring,
+		);
+	}
+
+	fn close(&mut self) {
+		self.finish();
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use geo::Point;
+	use ttf_parser::OutlineBuilder;
+
+	use super::{Builder, FontError};
+	use crate::{fill_rule::FillRule, CalcOptions, PolygonFastPrecalculator, Precision};
+
+	// Circle of four quadratic curves, counterclockwise or clockwise
+	fn circle(builder: &mut Builder, radius: f32, counterclockwise: bool) {
+		let y = if counterclockwise { radius } else { -radius };
+		builder.move_to(radius, 0.);
+		builder.quad_to(radius, y, 0., y);
+		builder.quad_to(-radius, y, -radius, 0.);
+		builder.quad_to(-radius, -y, 0., -y);
+		builder.quad_to(radius, -y, radius, 0.);
+		builder.close();
+	}
+
+	fn letter_o(hole_counterclockwise: bool, options: &CalcOptions) -> PolygonFastPrecalculator {
+		let mut builder = Builder::new(10.);
+		circle(&mut builder, 500., true);
+		circle(&mut builder, 300., hole_counterclockwise);
+		let contours = builder.contours('O').unwrap();
+		assert_eq!(contours.0.len(), 2);
+		PolygonFastPrecalculator::calc_with_options("glyph_O".to_owned(), contours, options)
+	}
+
+	#[test]
+	fn letter_o_with_hole() {
+		for &fill_rule in &FillRule::ALL {
+			let options = CalcOptions {
+				fill_rule,
+				..CalcOptions::default()
+			};
+			// Contours of opposite directions make a hole with every rule except the one of their sides
+			let tree = letter_o(false, &options);
+			assert!(!tree.is_inside(Point::new(0., 0.)));
+			assert!(!tree.is_inside(Point::new(150., -100.)));
+			assert_eq!(
+				tree.is_inside(Point::new(400., 0.)),
+				fill_rule != FillRule::Negative
+			);
+			assert_eq!(
+				tree.is_inside(Point::new(-280., -150.)),
+				fill_rule != FillRule::Negative
+			);
+			assert!(!tree.is_inside(Point::new(0., 510.)));
+			assert!(!tree.is_inside(Point::new(-600., 0.)));
+		}
+
+		// Contours of the same direction overlap, so only even-odd makes a hole
+		let tree = letter_o(true, &CalcOptions::default());
+		assert_eq!(tree.fill_rule, FillRule::NonZero);
+		assert!(tree.is_inside(Point::new(0., 0.)));
+		assert!(tree.is_inside(Point::new(400., 0.)));
+		let tree = letter_o(true, &CalcOptions {
+			fill_rule: FillRule::EvenOdd,
+			..CalcOptions::default()
+		});
+		assert!(!tree.is_inside(Point::new(0., 0.)));
+		assert!(tree.is_inside(Point::new(400., 0.)));
+	}
+
+	#[test]
+	fn options() {
+		let mut builder = Builder::new(10.);
+		circle(&mut builder, 500., true);
+		circle(&mut builder, 300., false);
+		let contours = builder.contours('O').unwrap();
+		for &max_edges_per_leaf in &[0, 16] {
+			let options = CalcOptions {
+				max_edges_per_leaf,
+				constants_precision: Precision::F32,
+				..CalcOptions::default()
+			};
+			let tree = letter_o(false, &options);
+			assert!(!tree.is_inside(Point::new(0., 0.)));
+			assert!(tree.is_inside(Point::new(400., 0.)));
+			let report = tree.verify(&contours, 2000);
+			assert!(report.is_ok_with_tolerance(1e-6), "{}", report);
+			assert_ne!(
+				tree.to_string(),
+				letter_o(false, &CalcOptions {
+					max_edges_per_leaf,
+					..CalcOptions::default()
+				})
+				.to_string()
+			);
+		}
+	}
+
+	#[test]
+	fn empty_outline() {
+		let mut builder = Builder::new(1.);
+		builder.move_to(0., 0.);
+		builder.line_to(1., 1.);
+		builder.close();
+		assert!(matches!(
+			builder.contours('-'),
+			Err(FontError::EmptyGlyph('-'))
+		));
+	}
+}
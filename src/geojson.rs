@@ -0,0 +1,359 @@
+use std::fmt;
+
+use geo::{Coordinate, Line, LineString, MultiPolygon, Polygon};
+use serde_json::{json, Value};
+
+use crate::{PolygonFastPrecalculator, PolygonFastPrecalculatorPart};
+
+#[derive(Debug)]
+pub enum GeoJsonError {
+	Json(serde_json::Error),
+	Format(String),
+}
+
+impl fmt::Display for GeoJsonError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			GeoJsonError::Json(err) => write!(f, "can't parse json: {}", err),
+			GeoJsonError::Format(message) => write!(f, "wrong GeoJSON: {}", message),
+		}
+	}
+}
+
+impl std::error::Error for GeoJsonError {}
+
+/// Reads all `Polygon` and `MultiPolygon` geometries from `FeatureCollection`, `Feature`, `GeometryCollection` or a bare geometry. First ring of every polygon is exterior, others are holes. Other geometries are skipped.
+pub fn from_geojson(s: &str) -> Result<MultiPolygon<f64>, GeoJsonError> {
+	let value: Value = serde_json::from_str(s).map_err(GeoJsonError::Json)?;
+	let mut result = Vec::new();
+	read_object(&value, &mut result)?;
+	Ok(MultiPolygon(result))
+}
+
+fn format_error(message: &str) -> GeoJsonError {
+	GeoJsonError::Format(message.to_owned())
+}
+
+fn read_object(value: &Value, result: &mut Vec<Polygon<f64>>) -> Result<(), GeoJsonError> {
+	let kind = value["type"]
+		.as_str()
+		.ok_or_else(|| format_error("object without `type`"))?;
+	let array = |key: &str| {
+		value[key]
+			.as_array()
+			.ok_or_else(|| GeoJsonError::Format(format!("`{}` without `{}` array", kind, key)))
+	};
+	match kind {
+		"FeatureCollection" => {
+			for feature in array("features")? {
+				read_object(feature, result)?;
+			}
+		},
+		"Feature" => {
+			if !value["geometry"].is_null() {
+				read_object(&value["geometry"], result)?;
+			}
+		},
+		"GeometryCollection" => {
+			for geometry in array("geometries")? {
+				read_object(geometry, result)?;
+			}
+		},
+		"Polygon" => result.extend(read_polygon(&value["coordinates"])?),
+		"MultiPolygon" => {
+			for polygon in array("coordinates")? {
+				result.extend(read_polygon(polygon)?);
+			}
+		},
+		_ => {},
+	}
+	Ok(())
+}
+
+fn read_polygon(value: &Value) -> Result<Option<Polygon<f64>>, GeoJsonError> {
+	let rings = value
+		.as_array()
+		.ok_or_else(|| format_error("polygon coordinates should be an array of rings"))?
+		.iter()
+		.map(read_ring)
+		.collect::<Result<Vec<_>, _>>()?;
+	let mut rings = rings.into_iter();
+	Ok(rings
+		.next()
+		.map(|exterior| Polygon::new(exterior, rings.collect())))
+}
+
+fn read_ring(value: &Value) -> Result<LineString<f64>, GeoJsonError> {
+	let error = || format_error("ring should be an array of positions [x, y]");
+	value
+		.as_array()
+		.ok_or_else(error)?
+		.iter()
+		.map(|position| {
+			let x = position[0].as_f64().ok_or_else(error)?;
+			let y = position[1].as_f64().ok_or_else(error)?;
+			Ok(Coordinate { x, y })
+		})
+		.collect::<Result<Vec<_>, _>>()
+		.map(LineString)
+}
+
+fn ring_coordinates(ring: &LineString<f64>) -> Value {
+	Value::Array(
+		ring.0
+			.iter()
+			.map(|coord| json!([coord.x, coord.y]))
+			.collect(),
+	)
+}
+
+fn polygon_coordinates(polygon: &Polygon<f64>) -> Value {
+	Value::Array(
+		std::iter::once(polygon.exterior())
+			.chain(polygon.interiors().iter())
+			.map(ring_coordinates)
+			.collect(),
+	)
+}
+
+fn line_coordinates(line: &Line<f64>) -> Value {
+	json!([[line.start.x, line.start.y], [line.end.x, line.end.y]])
+}
+
+pub fn to_geojson(polygon: &MultiPolygon<f64>) -> String {
+	json!({
+		"type": "MultiPolygon",
+		"coordinates": polygon.0.iter().map(polygon_coordinates).collect::<Vec<_>>(),
+	})
+	.to_string()
+}
+
+impl PolygonFastPrecalculator {
+	/// `FeatureCollection` with the cell of every leaf, the triangle or the edges inside of it, and every split line clipped to its cell, so the tree can be inspected in GIS viewers. Every feature has `kind` and `depth` properties.
+	pub fn cells_to_geojson(&self) -> String {
+		let mut features = Vec::new();
+		for cell in self.cells() {
+			let depth = cell.depth;
+			let feature = |kind: &str, geometry: Value| {
+				json!({
+					"type": "Feature",
+					"properties": { "kind": kind, "depth": depth },
+					"geometry": geometry,
+				})
+			};
+
+			match cell.part {
+				PolygonFastPrecalculatorPart::LineSplit { .. } => {
+					if let Some(line) = cell.split_line(self) {
+						features.push(feature(
+							"split",
+							json!({ "type": "LineString", "coordinates": line_coordinates(&line) }),
+						));
+					}
+				},
+				PolygonFastPrecalculatorPart::Triangle { .. } => {
+					features.push(feature(
+						"leaf",
+						json!({ "type": "Polygon", "coordinates": polygon_coordinates(&cell.polygon) }),
+					));
+					if let Some(triangle) = cell.triangle(self) {
+						features.push(feature(
+							"triangle",
+							json!({ "type": "Polygon", "coordinates": polygon_coordinates(&triangle) }),
+						));
+					}
+				},
+				PolygonFastPrecalculatorPart::Edges { .. } => {
+					features.push(feature(
+						"leaf",
+						json!({ "type": "Polygon", "coordinates": polygon_coordinates(&cell.polygon) }),
+					));
+					let edges = cell.edges(self);
+					features.push(feature(
+						"edges",
+						json!({ "type": "MultiLineString", "coordinates": edges.iter().map(line_coordinates).collect::<Vec<_>>() }),
+					));
+				},
+				PolygonFastPrecalculatorPart::None => {
+					features.push(feature(
+						"empty",
+						json!({ "type": "Polygon", "coordinates": polygon_coordinates(&cell.polygon) }),
+					));
+				},
+			}
+		}
+
+		json!({ "type": "FeatureCollection", "features": features }).to_string()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use geo::{LineString, MultiPolygon, Polygon};
+	use serde_json::Value;
+
+	use super::{from_geojson, to_geojson, GeoJsonError};
+	use crate::{
+		fill_rule::ring_signed_area, PolygonFastPrecalculator, PolygonFastPrecalculatorPart,
+	};
+
+	fn square_with_hole() -> Polygon<f64> {
+		Polygon::new(
+			LineString::from(vec![(0., 0.), (4., 0.), (4., 4.), (0., 4.), (0., 0.)]),
+			vec![LineString::from(vec![
+				(1., 1.),
+				(1., 3.),
+				(3., 3.),
+				(3., 1.),
+				(1., 1.),
+			])],
+		)
+	}
+
+	fn triangle() -> Polygon<f64> {
+		Polygon::new(
+			LineString::from(vec![(5., 0.), (6.5, 0.), (5., 1e-3), (5., 0.)]),
+			vec![],
+		)
+	}
+
+	#[test]
+	fn polygons() {
+		let polygon = from_geojson(
+			r#"{"type": "Polygon", "coordinates": [[[0, 0], [4, 0], [4, 4], [0, 4], [0, 0]], [[1, 1], [1, 3], [3, 3], [3, 1], [1, 1]]]}"#,
+		)
+		.unwrap();
+		assert_eq!(polygon, MultiPolygon(vec![square_with_hole()]));
+
+		let expected = MultiPolygon(vec![square_with_hole(), triangle()]);
+		for text in &[
+			r#"{"type": "MultiPolygon", "coordinates": [[[[0, 0], [4, 0], [4, 4], [0, 4], [0, 0]], [[1, 1], [1, 3], [3, 3], [3, 1], [1, 1]]], [], [[[5, 0], [6.5, 0], [5, 1e-3], [5, 0]]]]}"#,
+			// Features without polygons and extra coordinates are skipped
+			r#"{"type": "FeatureCollection", "features": [
+				{"type": "Feature", "properties": {}, "geometry": {"type": "Polygon", "coordinates": [[[0, 0, 1], [4, 0, 1], [4, 4, 1], [0, 4, 1], [0, 0, 1]], [[1, 1], [1, 3], [3, 3], [3, 1], [1, 1]]]}},
+				{"type": "Feature", "properties": {}, "geometry": null},
+				{"type": "Feature", "properties": {}, "geometry": {"type": "Point", "coordinates": [1, 2]}},
+				{"type": "Feature", "properties": {}, "geometry": {"type": "GeometryCollection", "geometries": [
+					{"type": "LineString", "coordinates": [[0, 0], [1, 1]]},
+					{"type": "Polygon", "coordinates": [[[5, 0], [6.5, 0], [5, 1e-3], [5, 0]]]}
+				]}}
+			]}"#,
+		] {
+			assert_eq!(from_geojson(text).unwrap(), expected, "{}", text);
+		}
+	}
+
+	#[test]
+	fn malformed() {
+		let format_error = |text: &str| match from_geojson(text) {
+			Err(GeoJsonError::Format(message)) => message,
+			other => panic!("{}: {:?}", text, other),
+		};
+		assert!(matches!(
+			from_geojson(r#"{"type": "Polygon""#),
+			Err(GeoJsonError::Json(_))
+		));
+		assert_eq!(
+			format_error(r#"{"coordinates": []}"#),
+			"object without `type`"
+		);
+		assert_eq!(
+			format_error(r#"{"type": "FeatureCollection"}"#),
+			"`FeatureCollection` without `features` array"
+		);
+		assert_eq!(
+			format_error(r#"{"type": "MultiPolygon", "coordinates": {}}"#),
+			"`MultiPolygon` without `coordinates` array"
+		);
+		assert_eq!(
+			format_error(r#"{"type": "Polygon", "coordinates": [0, 0]}"#),
+			"ring should be an array of positions [x, y]"
+		);
+		assert_eq!(
+			format_error(r#"{"type": "Polygon", "coordinates": [[[0, 0], [1], [0, 1]]]}"#),
+			"ring should be an array of positions [x, y]"
+		);
+		assert_eq!(
+			format_error(r#"{"type": "Polygon", "coordinates": [[[0, 0], ["1", 0], [0, 1]]]}"#),
+			"ring should be an array of positions [x, y]"
+		);
+		assert_eq!(
+			format_error(r#"{"type": "Polygon"}"#),
+			"polygon coordinates should be an array of rings"
+		);
+	}
+
+	#[test]
+	fn round_trip() {
+		for polygon in &[
+			MultiPolygon(vec![]),
+			MultiPolygon(vec![square_with_hole()]),
+			MultiPolygon(vec![square_with_hole(), triangle()]),
+			MultiPolygon(vec![Polygon::new(
+				LineString::from(vec![(0.1, -1e-20), (1. / 3., 2e20), (-0.7, 0.25)]),
+				vec![],
+			)]),
+		] {
+			let text = to_geojson(polygon);
+			assert_eq!(&from_geojson(&text).unwrap(), polygon, "{}", text);
+		}
+	}
+
+	#[test]
+	fn cells() {
+		let polygon = MultiPolygon(vec![square_with_hole(), triangle()]);
+		let tree = PolygonFastPrecalculator::calc("cells".to_owned(), polygon);
+		let text = tree.cells_to_geojson();
+		let value: Value = serde_json::from_str(&text).unwrap();
+		let features = value["features"].as_array().unwrap();
+		let count = |kind: &str| {
+			features
+				.iter()
+				.filter(|feature| feature["properties"]["kind"] == kind)
+				.count()
+		};
+
+		let cells = tree.cells();
+		let parts = |f: fn(&PolygonFastPrecalculatorPart) -> bool| {
+			cells.iter().filter(|cell| f(cell.part)).count()
+		};
+		let splits = parts(|part| matches!(part, PolygonFastPrecalculatorPart::LineSplit { .. }));
+		let triangles = parts(|part| matches!(part, PolygonFastPrecalculatorPart::Triangle { .. }));
+		let edges = parts(|part| matches!(part, PolygonFastPrecalculatorPart::Edges { .. }));
+		let empty = parts(|part| matches!(part, PolygonFastPrecalculatorPart::None));
+		assert!(splits > 0);
+		assert!(count("split") <= splits);
+		assert_eq!(count("leaf"), triangles + edges);
+		assert!(count("triangle") <= triangles);
+		assert_eq!(count("edges"), edges);
+		assert_eq!(count("empty"), empty);
+		for feature in features {
+			let depth = feature["properties"]["depth"].as_u64().unwrap() as usize;
+			assert!(cells.iter().any(|cell| cell.depth == depth));
+		}
+
+		// Cells of leaves cover the bounding rect, reading the collection back skips split lines and edges
+		let read = from_geojson(&text).unwrap();
+		assert_eq!(
+			read.0.len(),
+			count("leaf") + count("empty") + count("triangle")
+		);
+		let leaves = features
+			.iter()
+			.filter(|feature| {
+				matches!(
+					feature["properties"]["kind"].as_str(),
+					Some("leaf") | Some("empty")
+				)
+			})
+			.map(|feature| {
+				let polygon = from_geojson(&feature.to_string()).unwrap();
+				ring_signed_area(polygon.0[0].exterior()).abs()
+			})
+			.sum::<f64>();
+		let rect = tree.bounding_rect;
+		assert!(
+			(leaves - rect.width() * rect.height()).abs() < 1e-9 * rect.width() * rect.height()
+		);
+	}
+}
@@ -0,0 +1,629 @@
+use std::{collections::HashMap, fmt};
+
+use geo::{Coordinate, Point, Rect};
+
+use crate::{verify::Random, GridPrecalculator, PolygonFastPrecalculator};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum GlslError {
+	/// Byte offset in the text and message.
+	Parse(usize, String),
+	Eval(String),
+}
+
+impl fmt::Display for GlslError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			GlslError::Parse(position, message) => {
+				write!(f, "{} at position {}", message, position)
+			},
+			GlslError::Eval(message) => write!(f, "{}", message),
+		}
+	}
+}
+
+impl std::error::Error for GlslError {}
+
+/// Function `bool name(vec2 a)` of the GLSL subset that is generated by this crate: `if`/`else`, `return`, assignments, `vec2` constructors and components, arithmetic, comparisons, `true` and `false`, `&&`, `||` and `!`. It's used only in tests, to find errors of the code printer without GPU.
+#[derive(Clone, Debug)]
+pub struct GlslFunction {
+	pub name: String,
+	argument: String,
+	body: Vec<Statement>,
+}
+
+#[derive(Clone, Debug)]
+enum Statement {
+	If(Expr, Vec<Statement>, Vec<Statement>),
+	Return(Expr),
+	Assign(String, Expr),
+}
+
+#[derive(Clone, Debug)]
+enum Expr {
+	Number(f64),
+	Bool(bool),
+	Variable(String),
+	Vec2(Box<Expr>, Box<Expr>),
+	Component(Box<Expr>, char),
+	Not(Box<Expr>),
+	Neg(Box<Expr>),
+	Binary(Box<Expr>, &'static str, Box<Expr>),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Value {
+	Float(f64),
+	Vec2(f64, f64),
+	Bool(bool),
+}
+
+impl GlslFunction {
+	pub fn parse(text: &str) -> Result<Self, GlslError> {
+		let mut parser = Parser {
+			tokens: tokenize(text)?,
+			pos: 0,
+			len: text.len(),
+		};
+		parser.expect("bool")?;
+		let name = parser.identifier()?;
+		parser.expect("(")?;
+		parser.expect("vec2")?;
+		let argument = parser.identifier()?;
+		parser.expect(")")?;
+		let body = parser.block()?;
+		if parser.pos != parser.tokens.len() {
+			return Err(parser.error("unexpected text after function"));
+		}
+		Ok(Self {
+			name,
+			argument,
+			body,
+		})
+	}
+
+	pub fn eval(&self, point: Point<f64>) -> Result<bool, GlslError> {
+		let mut variables = HashMap::new();
+		variables.insert(self.argument.clone(), Value::Vec2(point.x(), point.y()));
+		match run(&self.body, &mut variables)? {
+			Some(Value::Bool(result)) => Ok(result),
+			Some(value) => Err(GlslError::Eval(format!(
+				"returned {:?} instead of bool",
+				value
+			))),
+			None => Err(GlslError::Eval("function ended without return".to_owned())),
+		}
+	}
+}
+
+impl PolygonFastPrecalculator {
+	/// Evaluates the generated code on points of the grid over the bounding rect and on random points, and returns points where it differs from `is_inside`. Literals are read as `f64`, so trees with rounded constants are checked by `is_inside_with_precision` instead.
+	fn check_generated_code(&self, samples: usize) -> Result<Vec<Point<f64>>, GlslError> {
+		check_code(&self.to_string(), &self.bounding_rect, samples, |point| {
+			self.is_inside(point)
+		})
+	}
+}
+
+impl GridPrecalculator {
+	/// Same as `PolygonFastPrecalculator::check_generated_code`.
+	pub(crate) fn check_generated_code(
+		&self,
+		samples: usize,
+	) -> Result<Vec<Point<f64>>, GlslError> {
+		check_code(&self.to_string(), &self.bounding_rect, samples, |point| {
+			self.is_inside(point)
+		})
+	}
+}
+
+fn check_code(
+	code: &str,
+	rect: &Rect<f64>,
+	samples: usize,
+	is_inside: impl Fn(Point<f64>) -> bool,
+) -> Result<Vec<Point<f64>>, GlslError> {
+	let function = GlslFunction::parse(code)?;
+
+	let side = (samples as f64 / 2.).sqrt().ceil().max(1.) as usize;
+	let mut random = Random::new(samples as u64);
+	let mut mismatches = Vec::new();
+	for i in 0..samples {
+		// Points slightly outside of the bounding rect check the bounds test too
+		let (tx, ty) = if i % 2 == 0 {
+			let cell = i / 2;
+			(
+				(cell % side) as f64 / side as f64,
+				(cell / side % side) as f64 / side as f64,
+			)
+		} else {
+			(random.next_f64(), random.next_f64())
+		};
+		let point = Point(Coordinate {
+			x: rect.min().x + (tx * 1.1 - 0.05) * rect.width(),
+			y: rect.min().y + (ty * 1.1 - 0.05) * rect.height(),
+		});
+		if function.eval(point)? != is_inside(point) {
+			mismatches.push(point);
+		}
+	}
+	Ok(mismatches)
+}
+
+fn run(
+	statements: &[Statement],
+	variables: &mut HashMap<String, Value>,
+) -> Result<Option<Value>, GlslError> {
+	for statement in statements {
+		match statement {
+			Statement::If(condition, then, otherwise) => {
+				let branch = match eval(condition, variables)? {
+					Value::Bool(true) => then,
+					Value::Bool(false) => otherwise,
+					value => {
+						return Err(GlslError::Eval(format!(
+							"condition is {:?} instead of bool",
+							value
+						)))
+					},
+				};
+				if let Some(result) = run(branch, variables)? {
+					return Ok(Some(result));
+				}
+			},
+			Statement::Return(expr) => return Ok(Some(eval(expr, variables)?)),
+			Statement::Assign(name, expr) => {
+				let value = eval(expr, variables)?;
+				variables.insert(name.clone(), value);
+			},
+		}
+	}
+	Ok(None)
+}
+
+fn eval(expr: &Expr, variables: &HashMap<String, Value>) -> Result<Value, GlslError> {
+	use Value::*;
+	let type_error = |message: String| Err(GlslError::Eval(message));
+	Ok(match expr {
+		Expr::Number(x) => Float(*x),
+		Expr::Bool(x) => Bool(*x),
+		Expr::Variable(name) => match variables.get(name) {
+			Some(value) => *value,
+			None => return type_error(format!("unknown variable `{}`", name)),
+		},
+		Expr::Vec2(x, y) => match (eval(x, variables)?, eval(y, variables)?) {
+			(Float(x), Float(y)) => Vec2(x, y),
+			(x, y) => return type_error(format!("vec2({:?}, {:?})", x, y)),
+		},
+		Expr::Component(value, component) => match (eval(value, variables)?, *component) {
+			(Vec2(x, _), 'x') => Float(x),
+			(Vec2(_, y), 'y') => Float(y),
+			(value, _) => return type_error(format!("{:?}.{}", value, component)),
+		},
+		Expr::Not(value) => match eval(value, variables)? {
+			Bool(value) => Bool(!value),
+			value => return type_error(format!("!{:?}", value)),
+		},
+		Expr::Neg(value) => match eval(value, variables)? {
+			Float(x) => Float(-x),
+			Vec2(x, y) => Vec2(-x, -y),
+			value => return type_error(format!("-{:?}", value)),
+		},
+		// Short-circuit like in GLSL
+		Expr::Binary(a, "&&", b) | Expr::Binary(a, "||", b) => {
+			let is_and = matches!(expr, Expr::Binary(_, "&&", _));
+			match eval(a, variables)? {
+				Bool(value) if value != is_and => Bool(value),
+				Bool(_) => match eval(b, variables)? {
+					Bool(value) => Bool(value),
+					value => return type_error(format!("logical operation with {:?}", value)),
+				},
+				value => return type_error(format!("logical operation with {:?}", value)),
+			}
+		},
+		Expr::Binary(a, op, b) => {
+			let (a, b) = (eval(a, variables)?, eval(b, variables)?);
+			let arithmetic = |x: f64, y: f64| match *op {
+				"+" => x + y,
+				"-" => x - y,
+				"*" => x * y,
+				_ => x / y,
+			};
+			match (a, b, *op) {
+				(Float(x), Float(y), "<") => Bool(x < y),
+				(Float(x), Float(y), "<=") => Bool(x <= y),
+				(Float(x), Float(y), ">") => Bool(x > y),
+				(Float(x), Float(y), ">=") => Bool(x >= y),
+				(Float(x), Float(y), "==") => Bool(x == y),
+				(Float(x), Float(y), "!=") => Bool(x != y),
+				(Float(x), Float(y), _) => Float(arithmetic(x, y)),
+				(Bool(x), Bool(y), "==") => Bool(x == y),
+				(Bool(x), Bool(y), "!=") => Bool(x != y),
+				(Vec2(x1, y1), Vec2(x2, y2), "+")
+				| (Vec2(x1, y1), Vec2(x2, y2), "-")
+				| (Vec2(x1, y1), Vec2(x2, y2), "*")
+				| (Vec2(x1, y1), Vec2(x2, y2), "/") => Vec2(arithmetic(x1, x2), arithmetic(y1, y2)),
+				_ => return type_error(format!("{:?} {} {:?}", a, op, b)),
+			}
+		},
+	})
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+	Identifier(String),
+	Number(f64),
+	Symbol(&'static str),
+}
+
+const SYMBOLS: &[&str] = &[
+	"&&", "||", "<=", ">=", "==", "!=", "<", ">", "!", "+", "-", "*", "/", "=", "(", ")", "{", "}",
+	",", ";", ".",
+];
+
+fn tokenize(text: &str) -> Result<Vec<(Token, usize)>, GlslError> {
+	let mut result = Vec::new();
+	let mut pos = 0;
+	while pos < text.len() {
+		let rest = &text[pos..];
+		let c = rest.chars().next().unwrap();
+		if c.is_whitespace() {
+			pos += c.len_utf8();
+		} else if c.is_ascii_alphabetic() || c == '_' {
+			let len = rest
+				.find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+				.unwrap_or_else(|| rest.len());
+			result.push((Token::Identifier(rest[..len].to_owned()), pos));
+			pos += len;
+		} else if c.is_ascii_digit()
+			|| (c == '.' && rest[1..].starts_with(|c: char| c.is_ascii_digit()))
+		{
+			let mut len = 0;
+			let bytes = rest.as_bytes();
+			while len < bytes.len() {
+				let after_exponent = len > 0 && matches!(bytes[len - 1], b'e' | b'E');
+				let is_number = match bytes[len] {
+					b'0'..=b'9' | b'.' | b'e' | b'E' => true,
+					b'-' | b'+' => after_exponent,
+					_ => false,
+				};
+				if is_number {
+					len += 1;
+				} else {
+					break;
+				}
+			}
+			let literal = &rest[..len];
+			result.push((Token::Number(parse_number(literal, pos)?), pos));
+			pos += len;
+		} else {
+			let symbol = *SYMBOLS
+				.iter()
+				.find(|symbol| rest.starts_with(**symbol))
+				.ok_or_else(|| GlslError::Parse(pos, format!("unexpected character `{}`", c)))?;
+			result.push((Token::Symbol(symbol), pos));
+			pos += symbol.len();
+		}
+	}
+	Ok(result)
+}
+
+fn parse_number(literal: &str, pos: usize) -> Result<f64, GlslError> {
+	literal
+		.parse::<f64>()
+		.map_err(|_| GlslError::Parse(pos, format!("can't parse number `{}`", literal)))
+}
+
+struct Parser {
+	tokens: Vec<(Token, usize)>,
+	pos: usize,
+	len: usize,
+}
+
+impl Parser {
+	fn error(&self, message: &str) -> GlslError {
+		let position = self
+			.tokens
+			.get(self.pos)
+			.map(|(_, position)| *position)
+			.unwrap_or(self.len);
+		GlslError::Parse(position, message.to_owned())
+	}
+
+	fn peek(&self) -> Option<&Token> {
+		self.tokens.get(self.pos).map(|(token, _)| token)
+	}
+
+	fn is_next(&self, word: &str) -> bool {
+		match self.peek() {
+			Some(Token::Symbol(symbol)) => *symbol == word,
+			Some(Token::Identifier(identifier)) => identifier == word,
+			_ => false,
+		}
+	}
+
+	fn consume(&mut self, word: &str) -> bool {
+		let result = self.is_next(word);
+		if result {
+			self.pos += 1;
+		}
+		result
+	}
+
+	fn expect(&mut self, word: &str) -> Result<(), GlslError> {
+		if self.consume(word) {
+			Ok(())
+		} else {
+			Err(self.error(&format!("expected `{}`", word)))
+		}
+	}
+
+	fn identifier(&mut self) -> Result<String, GlslError> {
+		match self.peek() {
+			Some(Token::Identifier(identifier)) => {
+				let identifier = identifier.clone();
+				self.pos += 1;
+				Ok(identifier)
+			},
+			_ => Err(self.error("expected identifier")),
+		}
+	}
+
+	fn block(&mut self) -> Result<Vec<Statement>, GlslError> {
+		self.expect("{")?;
+		let mut result = Vec::new();
+		while !self.consume("}") {
+			if self.peek().is_none() {
+				return Err(self.error("expected `}`"));
+			}
+			result.push(self.statement()?);
+		}
+		Ok(result)
+	}
+
+	fn statement(&mut self) -> Result<Statement, GlslError> {
+		if self.consume("if") {
+			self.expect("(")?;
+			let condition = self.expr()?;
+			self.expect(")")?;
+			let then = self.block()?;
+			let otherwise = if self.consume("else") {
+				if self.is_next("if") {
+					vec![self.statement()?]
+				} else {
+					self.block()?
+				}
+			} else {
+				Vec::new()
+			};
+			Ok(Statement::If(condition, then, otherwise))
+		} else if self.consume("return") {
+			let expr = self.expr()?;
+			self.expect(";")?;
+			Ok(Statement::Return(expr))
+		} else {
+			let name = self.identifier()?;
+			self.expect("=")?;
+			let expr = self.expr()?;
+			self.expect(";")?;
+			Ok(Statement::Assign(name, expr))
+		}
+	}
+
+	fn expr(&mut self) -> Result<Expr, GlslError> {
+		self.binary(0)
+	}
+
+	// Operators from the lowest precedence
+	fn binary(&mut self, level: usize) -> Result<Expr, GlslError> {
+		const LEVELS: &[&[&str]] = &[
+			&["||"],
+			&["&&"],
+			&["==", "!="],
+			&["<=", ">=", "<", ">"],
+			&["+", "-"],
+			&["*", "/"],
+		];
+		if level == LEVELS.len() {
+			return self.unary();
+		}
+
+		let mut result = self.binary(level + 1)?;
+		while let Some(&op) = LEVELS[level].iter().find(|op| self.is_next(op)) {
+			self.pos += 1;
+			let right = self.binary(level + 1)?;
+			result = Expr::Binary(Box::new(result), op, Box::new(right));
+		}
+		Ok(result)
+	}
+
+	fn unary(&mut self) -> Result<Expr, GlslError> {
+		if self.consume("!") {
+			Ok(Expr::Not(Box::new(self.unary()?)))
+		} else if self.consume("-") {
+			Ok(Expr::Neg(Box::new(self.unary()?)))
+		} else if self.consume("+") {
+			self.unary()
+		} else {
+			self.postfix()
+		}
+	}
+
+	fn postfix(&mut self) -> Result<Expr, GlslError> {
+		let mut result = self.primary()?;
+		while self.consume(".") {
+			let component = self.identifier()?;
+			result = match component.as_str() {
+				"x" | "r" => Expr::Component(Box::new(result), 'x'),
+				"y" | "g" => Expr::Component(Box::new(result), 'y'),
+				_ => return Err(self.error("unsupported component")),
+			};
+		}
+		Ok(result)
+	}
+
+	fn primary(&mut self) -> Result<Expr, GlslError> {
+		match self.peek().cloned() {
+			Some(Token::Number(x)) => {
+				self.pos += 1;
+				Ok(Expr::Number(x))
+			},
+			Some(Token::Identifier(name)) if name == "true" || name == "false" => {
+				self.pos += 1;
+				Ok(Expr::Bool(name == "true"))
+			},
+			Some(Token::Identifier(name)) if name == "vec2" => {
+				self.pos += 1;
+				self.expect("(")?;
+				let x = self.expr()?;
+				self.expect(",")?;
+				let y = self.expr()?;
+				self.expect(")")?;
+				Ok(Expr::Vec2(Box::new(x), Box::new(y)))
+			},
+			Some(Token::Identifier(name)) => {
+				self.pos += 1;
+				Ok(Expr::Variable(name))
+			},
+			Some(Token::Symbol("(")) => {
+				self.pos += 1;
+				let result = self.expr()?;
+				self.expect(")")?;
+				Ok(result)
+			},
+			_ => Err(self.error("expected expression")),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use geo::{LineString, MultiPolygon, Point, Polygon};
+
+	use super::GlslFunction;
+	use crate::{
+		reference_edges, CalcOptions, CrossingEdge, FillRule, LineSplitCheckGeneralized,
+		PolygonFastPrecalculator, PolygonFastPrecalculatorPart,
+	};
+
+	fn polygon(exterior: &[(f64, f64)], holes: &[&[(f64, f64)]]) -> MultiPolygon<f64> {
+		MultiPolygon(vec![Polygon::new(
+			LineString::from(exterior.to_vec()),
+			holes
+				.iter()
+				.map(|hole| LineString::from(hole.to_vec()))
+				.collect(),
+		)])
+	}
+
+	#[test]
+	fn literals_are_f64() {
+		let function = GlslFunction::parse("bool f(vec2 a) { return a.x < 1e-1; }").unwrap();
+		assert_eq!(function.name, "f");
+		assert!(!function.eval(Point::new(0.1, 0.)).unwrap());
+		assert!(function.eval(Point::new(0.09999999, 0.)).unwrap());
+	}
+
+	#[test]
+	fn bool_literals() {
+		let function = GlslFunction::parse(
+			"bool f(vec2 a) { if (a.x < 0.) { return false; } else { return true != (a.y < 0.); } }",
+		)
+		.unwrap();
+		assert!(!function.eval(Point::new(-1., 0.)).unwrap());
+		assert!(function.eval(Point::new(1., 1.)).unwrap());
+		assert!(!function.eval(Point::new(1., -1.)).unwrap());
+	}
+
+	#[test]
+	fn generated_code_is_the_tree() {
+		// Bounding rects start at `0.1`, that is not exact in `f32`
+		let polygons = [
+			polygon(
+				&[
+					(0.1, 0.1),
+					(5.1, 0.1),
+					(5.1, 3.1),
+					(4.1, 3.1),
+					(4.1, 1.1),
+					(3.1, 1.1),
+					(3.1, 3.1),
+					(2.1, 3.1),
+					(2.1, 1.1),
+					(1.1, 1.1),
+					(1.1, 3.1),
+					(0.1, 3.1),
+				],
+				&[],
+			),
+			polygon(&[(0.1, 0.1), (4.1, 0.1), (4.1, 4.1), (0.1, 4.1)], &[&[
+				(1.1, 1.1),
+				(1.1, 3.1),
+				(3.1, 3.1),
+				(3.1, 1.1),
+			]]),
+			polygon(
+				&[
+					(0., 1.),
+					(-0.5878, -0.809),
+					(0.9511, 0.309),
+					(-0.9511, 0.309),
+					(0.5878, -0.809),
+				],
+				&[],
+			),
+			polygon(
+				&[
+					(0.1, 0.3),
+					(2.3, 0.1),
+					(1.7, 1.9),
+					(3.1, 2.2),
+					(0.9, 3.7),
+					(1.2, 1.4),
+				],
+				&[],
+			),
+		];
+
+		let mut has_greater = false;
+		for polygon in &polygons {
+			for &fill_rule in &FillRule::ALL {
+				for &max_edges_per_leaf in &[0, 8] {
+					let options = CalcOptions {
+						fill_rule,
+						max_edges_per_leaf,
+						..CalcOptions::default()
+					};
+					let tree = PolygonFastPrecalculator::calc_with_options(
+						"test".to_owned(),
+						polygon.clone(),
+						&options,
+					);
+					tree.parts.visit(&mut |part, _| match part {
+						PolygonFastPrecalculatorPart::Triangle { checks } => {
+							has_greater |= checks.iter().any(|check| {
+								matches!(check, LineSplitCheckGeneralized::Greater(_))
+							});
+						},
+						_ => {},
+					});
+
+					let mismatches = tree.check_generated_code(2000).unwrap();
+					assert!(mismatches.is_empty(), "{:?}\n{}", mismatches, tree);
+				}
+			}
+
+			// Splits are cheaper than `Edges` leaves for these polygons, so the code of a leaf is checked on the whole polygon
+			let mut tree = PolygonFastPrecalculator::calc("test".to_owned(), polygon.clone());
+			tree.parts = PolygonFastPrecalculatorPart::Edges {
+				edges: reference_edges(polygon)
+					.into_iter()
+					.filter_map(CrossingEdge::calc)
+					.collect(),
+			};
+			let mismatches = tree.check_generated_code(2000).unwrap();
+			assert!(mismatches.is_empty(), "{:?}\n{}", mismatches, tree);
+		}
+		assert!(has_greater);
+	}
+}
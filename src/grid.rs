@@ -0,0 +1,259 @@
+use std::fmt;
+
+use geo::{prelude::Area, Coordinate, LineString, MultiPolygon, Point, Polygon, Rect};
+use geo_clipper::Clipper;
+#[cfg(feature = "serialize")]
+use serde::{Deserialize, Serialize};
+
+use crate::{
+	edges, fit_point_into_default_borders, normalize, verify::verify_with, CrossingEdge, FillRule,
+	Normalization, VerificationReport,
+};
+
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub enum GridCell {
+	Empty,
+	Full,
+	/// Point is inside when it crosses odd count of edges of the polygon clipped to the cell.
+	Mixed(Vec<CrossingEdge>),
+}
+
+/// Alternative to the tree for polygons with many vertices, where the tree gets deep: uniform grid over `[0, 1]²`, every cell of it is empty, full, or checks crossing number over the few edges that lie in it.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct GridPrecalculator {
+	pub name: String,
+	pub bounding_rect: Rect<f64>,
+	pub fill_rule: FillRule,
+	/// Count of cells along each side.
+	pub size: usize,
+	/// Row by row from the bottom.
+	pub cells: Vec<GridCell>,
+}
+
+impl GridPrecalculator {
+	pub fn calc(
+		name: String,
+		polygon: MultiPolygon<f64>,
+		fill_rule: FillRule,
+		size: usize,
+	) -> Self {
+		assert!(size > 0, "grid should have at least one cell");
+		let (polygon, br) = normalize(&polygon, fill_rule);
+		let step = 1. / size as f64;
+		let clip = |polygon: &MultiPolygon<f64>, min: Coordinate<f64>, max: Coordinate<f64>| {
+			let rect = Polygon::new(
+				LineString(vec![
+					min,
+					Coordinate { x: max.x, y: min.y },
+					max,
+					Coordinate { x: min.x, y: max.y },
+				]),
+				vec![],
+			);
+			polygon.intersection(&rect, 6000000000.0)
+		};
+
+		// Polygon is clipped to rows first, so every cell is clipped from the few edges of its row instead of the whole polygon
+		let mut cells = Vec::with_capacity(size * size);
+		for row in 0..size {
+			let y = row as f64 * step;
+			let row_polygon = clip(&polygon, Coordinate { x: 0., y }, Coordinate {
+				x: 1.,
+				y: y + step,
+			});
+			for column in 0..size {
+				let x = column as f64 * step;
+				let clipped = if row_polygon.0.is_empty() {
+					MultiPolygon(vec![])
+				} else {
+					clip(&row_polygon, Coordinate { x, y }, Coordinate {
+						x: x + step,
+						y: y + step,
+					})
+				};
+				let area = clipped.unsigned_area();
+				cells.push(if clipped.0.is_empty() || area < step * step * 1e-9 {
+					GridCell::Empty
+				} else if area > step * step * (1. - 1e-9) {
+					GridCell::Full
+				} else {
+					GridCell::Mixed(edges(&clipped).filter_map(CrossingEdge::calc).collect())
+				});
+			}
+		}
+
+		Self {
+			name,
+			bounding_rect: br,
+			fill_rule,
+			size,
+			cells,
+		}
+	}
+
+	pub fn is_inside(&self, point: Point<f64>) -> bool {
+		let point = fit_point_into_default_borders(point, &self.bounding_rect);
+		if !(0. <= point.x() && point.x() <= 1.) {
+			return false;
+		}
+		if !(0. <= point.y() && point.y() <= 1.) {
+			return false;
+		}
+
+		let column = ((point.x() * self.size as f64) as usize).min(self.size - 1);
+		let row = ((point.y() * self.size as f64) as usize).min(self.size - 1);
+		match &self.cells[row * self.size + column] {
+			GridCell::Empty => false,
+			GridCell::Full => true,
+			GridCell::Mixed(edges) => edges.iter().filter(|e| e.is_crossed(point)).count() % 2 == 1,
+		}
+	}
+
+	/// Same as `PolygonFastPrecalculator::verify`.
+	pub fn verify(&self, original: &MultiPolygon<f64>, samples: usize) -> VerificationReport {
+		verify_with(original, self.fill_rule, samples, |point| {
+			self.is_inside(point)
+		})
+	}
+}
+
+/// `is_inside_<name>` function that finds the cell by binary search over rows and columns, and checks it in the same way as leaves of the tree, so the code has no arrays and loops and works in GLSL ES 1.00.
+impl fmt::Display for GridPrecalculator {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		// Same comparisons as `is_inside`, `a * size` is less than integer `i` exactly when its integer part is
+		fn print_range(
+			grid: &GridPrecalculator,
+			row: Option<usize>,
+			range: (usize, usize),
+			indent: usize,
+			f: &mut fmt::Formatter<'_>,
+		) -> fmt::Result {
+			let tab = " ".repeat(indent);
+			let (start, end) = range;
+			if end - start > 1 {
+				let middle = (start + end) / 2;
+				let axis = if row.is_none() { "y" } else { "x" };
+				writeln!(
+					f,
+					"{}if (a.{} * {}. < {}.) {{",
+					tab, axis, grid.size, middle
+				)?;
+				print_range(grid, row, (start, middle), indent + 2, f)?;
+				writeln!(f, "{}}} else {{", tab)?;
+				print_range(grid, row, (middle, end), indent + 2, f)?;
+				return writeln!(f, "{}}}", tab);
+			}
+
+			let row = match row {
+				Some(row) => row,
+				None => return print_range(grid, Some(start), (0, grid.size), indent, f),
+			};
+			match &grid.cells[row * grid.size + start] {
+				GridCell::Empty => writeln!(f, "{}return false;", tab),
+				GridCell::Full => writeln!(f, "{}return true;", tab),
+				// Parity of crossed edges
+				GridCell::Mixed(edges) if edges.is_empty() => writeln!(f, "{}return false;", tab),
+				GridCell::Mixed(edges) => {
+					let edges = edges
+						.iter()
+						.map(|edge| edge.to_string())
+						.collect::<Vec<_>>();
+					writeln!(f, "{}return {};", tab, edges.join(" != "))
+				},
+			}
+		}
+
+		writeln!(f, "bool is_inside_{}(vec2 a) {{", self.name)?;
+		writeln!(f, "  {}", Normalization(&self.bounding_rect))?;
+		writeln!(
+			f,
+			"  if (0. <= a.x && a.x <= 1. && 0. <= a.y && a.y <= 1.) {{"
+		)?;
+		print_range(self, None, (0, self.size), 4, f)?;
+		writeln!(f, "  }} else {{")?;
+		writeln!(f, "    return false;")?;
+		writeln!(f, "  }}")?;
+		writeln!(f, "}}")
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use geo::{LineString, MultiPolygon, Polygon};
+
+	use super::GridPrecalculator;
+	use crate::FillRule;
+
+	fn polygon(exterior: &[(f64, f64)], holes: &[&[(f64, f64)]]) -> MultiPolygon<f64> {
+		MultiPolygon(vec![Polygon::new(
+			LineString::from(exterior.to_vec()),
+			holes
+				.iter()
+				.map(|hole| LineString::from(hole.to_vec()))
+				.collect(),
+		)])
+	}
+
+	#[test]
+	fn fixtures() {
+		let comb = polygon(
+			&[
+				(0., 0.),
+				(5., 0.),
+				(5., 3.),
+				(4., 3.),
+				(4., 1.),
+				(3., 1.),
+				(3., 3.),
+				(2., 3.),
+				(2., 1.),
+				(1., 1.),
+				(1., 3.),
+				(0., 3.),
+			],
+			&[],
+		);
+		let holed = polygon(&[(0.1, 0.1), (4.1, 0.1), (4.1, 4.1), (0.1, 4.1)], &[&[
+			(1.1, 1.1),
+			(1.1, 3.1),
+			(3.1, 3.1),
+			(3.1, 1.1),
+		]]);
+		let pentagram = polygon(
+			&[
+				(0., 1.),
+				(-0.5878, -0.809),
+				(0.9511, 0.309),
+				(-0.9511, 0.309),
+				(0.5878, -0.809),
+			],
+			&[],
+		);
+
+		for polygon in &[comb, holed, pentagram] {
+			for &fill_rule in &FillRule::ALL {
+				for &size in &[1, 4, 7] {
+					let grid = GridPrecalculator::calc(
+						"test".to_owned(),
+						polygon.clone(),
+						fill_rule,
+						size,
+					);
+					let report = grid.verify(polygon, 2000);
+					assert!(
+						report.is_ok_with_tolerance(1e-9),
+						"{}, size {}\n{}",
+						fill_rule.name(),
+						size,
+						report
+					);
+
+					let mismatches = grid.check_generated_code(2000).unwrap();
+					assert!(mismatches.is_empty(), "{:?}\n{}", mismatches, grid);
+				}
+			}
+		}
+	}
+}
@@ -0,0 +1,204 @@
+use std::{fs::File, io, io::BufWriter, path::Path};
+
+use geo::{prelude::*, Coordinate, MultiPolygon, Point, Polygon};
+use glam::Vec2;
+
+use crate::{mymax, mymin};
+
+pub struct ImageIterator {
+	x: usize,
+	y: usize,
+	w: usize,
+	h: usize,
+}
+
+impl Iterator for ImageIterator {
+	type Item = (usize, usize, Vec2);
+
+	fn next(&mut self) -> Option<Self::Item> {
+		if self.y == self.h {
+			return None;
+		}
+
+		let min = std::cmp::min(self.w, self.h) as f32;
+		let to_return = (
+			self.x,
+			self.y,
+			(Vec2::new(self.x as f32, self.y as f32) / min * 2. - Vec2::new(1., 1.)),
+		);
+
+		self.x += 1;
+		if self.x == self.w {
+			self.y += 1;
+			self.x = 0;
+		}
+		Some(to_return)
+	}
+}
+
+/// RGB image, row by row from the top.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Image {
+	w: usize,
+	h: usize,
+	data: Vec<u8>,
+}
+
+impl Image {
+	pub fn new(w: usize, h: usize) -> Self {
+		Self {
+			w,
+			h,
+			data: vec![0; w * h * 3],
+		}
+	}
+
+	pub fn iter(&self) -> ImageIterator {
+		ImageIterator {
+			x: 0,
+			y: 0,
+			w: self.w,
+			h: self.h,
+		}
+	}
+
+	pub fn width(&self) -> usize {
+		self.w
+	}
+
+	pub fn height(&self) -> usize {
+		self.h
+	}
+
+	pub fn data(&self) -> &[u8] {
+		&self.data
+	}
+
+	pub fn get_pixel(&self, x: usize, y: usize) -> (u8, u8, u8) {
+		let offset = (x + y * self.w) * 3;
+		(
+			self.data[offset],
+			self.data[offset + 1],
+			self.data[offset + 2],
+		)
+	}
+
+	pub fn set_pixel(&mut self, x: usize, y: usize, color: (u8, u8, u8)) {
+		let offset = (x + y * self.w) * 3;
+		self.data[offset + 0] = color.0;
+		self.data[offset + 1] = color.1;
+		self.data[offset + 2] = color.2;
+	}
+
+	pub fn fill(&mut self, color: (u8, u8, u8)) {
+		for pixel in self.data.chunks_mut(3) {
+			pixel.copy_from_slice(&[color.0, color.1, color.2]);
+		}
+	}
+
+	/// Sets pixels along the line, coordinates are in pixels and can lie outside of the image.
+	pub fn draw_line(&mut self, from: (f64, f64), to: (f64, f64), color: (u8, u8, u8)) {
+		let steps = mymax((to.0 - from.0).abs(), (to.1 - from.1).abs()).ceil() as usize;
+		for i in 0..=steps {
+			let t = if steps == 0 {
+				0.
+			} else {
+				i as f64 / steps as f64
+			};
+			self.draw_point(
+				(from.0 + (to.0 - from.0) * t, from.1 + (to.1 - from.1) * t),
+				0,
+				color,
+			);
+		}
+	}
+
+	/// Square with side `2 * radius + 1` pixels around the point.
+	pub fn draw_point(&mut self, point: (f64, f64), radius: usize, color: (u8, u8, u8)) {
+		let (x, y) = (point.0.floor() as isize, point.1.floor() as isize);
+		let radius = radius as isize;
+		for py in y - radius..=y + radius {
+			for px in x - radius..=x + radius {
+				if px >= 0 && py >= 0 && (px as usize) < self.w && (py as usize) < self.h {
+					self.set_pixel(px as usize, py as usize, color);
+				}
+			}
+		}
+	}
+
+	/// Count of pixels with different colors, images should have the same size.
+	pub fn count_differences(&self, other: &Image) -> usize {
+		self.data
+			.chunks(3)
+			.zip(other.data.chunks(3))
+			.filter(|(a, b)| a != b)
+			.count()
+	}
+
+	pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+		let file = File::create(path)?;
+		let ref mut wr = BufWriter::new(file);
+
+		let mut encoder = png::Encoder::new(wr, self.w as u32, self.h as u32);
+		encoder.set_color(png::ColorType::RGB);
+		encoder.set_depth(png::BitDepth::Eight);
+		let mut writer = encoder.write_header()?;
+
+		writer.write_image_data(&self.data)?;
+		Ok(())
+	}
+}
+
+pub struct PolygonDrawer {
+	image: Image,
+	polygons: Vec<(MultiPolygon<f64>, (u8, u8, u8))>,
+}
+
+impl PolygonDrawer {
+	pub fn new(size: usize) -> Self {
+		Self {
+			image: Image::new(size + 20, size + 20),
+			polygons: vec![],
+		}
+	}
+
+	pub fn add_polygon(&mut self, polygon: Polygon<f64>, color: (u8, u8, u8)) {
+		self.polygons.push((MultiPolygon::from(polygon), color));
+	}
+
+	pub fn add_multipolygon(&mut self, polygon: MultiPolygon<f64>, color: (u8, u8, u8)) {
+		self.polygons.push((polygon, color));
+	}
+
+	pub fn draw_and_save(&mut self, filename: &str) {
+		let mut rect = self.polygons[0].0.bounding_rect().unwrap();
+		for current in self
+			.polygons
+			.iter()
+			.filter_map(|(poly, _)| poly.bounding_rect())
+		{
+			let minx = mymin(current.min().x, rect.min().x);
+			let miny = mymin(current.min().y, rect.min().y);
+			rect.set_min(Coordinate::from((minx, miny)));
+
+			let maxx = mymax(current.max().x, rect.max().x);
+			let maxy = mymax(current.max().y, rect.max().y);
+			rect.set_max(Coordinate::from((maxx, maxy)));
+		}
+		for (x, y, _) in self.image.iter() {
+			let point = Point::new(
+				(x as f64 - 10.) / (self.image.w - 20) as f64 * rect.width(),
+				(y as f64 - 10.) / (self.image.w - 20) as f64 * rect.height(),
+			) + rect.min().into();
+
+			self.image.set_pixel(x, y, (255, 255, 255));
+			for (poly, color) in self.polygons.iter() {
+				if poly.contains(&point) {
+					self.image.set_pixel(x, y, *color);
+					break;
+				}
+			}
+		}
+		self.image.save(filename).unwrap();
+	}
+}
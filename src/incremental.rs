@@ -0,0 +1,474 @@
+use geo::{Coordinate, Line, MultiPolygon, Point, Rect};
+use line_intersection::LineInterval;
+
+use crate::{
+	cells::clip_convex, edges, fill_rule::winding_number, fit_into_default_borders, resolve,
+	CalcOptions, CanonicalLines, CrossingEdge, LineSplitCheck, LineSplitCheckGeneralized,
+	PolygonFastPrecalculator, PolygonFastPrecalculatorPart, Precision,
+};
+
+struct Changes<'a> {
+	previous: &'a MultiPolygon<f64>,
+	current: &'a MultiPolygon<f64>,
+	edges: Vec<Line<f64>>,
+}
+
+impl Changes<'_> {
+	// Inside of the cell changed if a changed edge crosses it, or if the whole cell was covered or uncovered by a moved edge
+	fn touch(&self, cell: &[Coordinate<f64>]) -> bool {
+		if cell.len() < 3 {
+			return false;
+		}
+
+		let center = Point(Coordinate {
+			x: cell.iter().map(|coord| coord.x).sum::<f64>() / cell.len() as f64,
+			y: cell.iter().map(|coord| coord.y).sum::<f64>() / cell.len() as f64,
+		});
+		if (winding_number(self.previous, center) != 0)
+			!= (winding_number(self.current, center) != 0)
+		{
+			return true;
+		}
+
+		self.edges.iter().any(|edge| {
+			is_in_convex(cell, edge.start)
+				|| is_in_convex(cell, edge.end)
+				|| (0..cell.len()).any(|i| {
+					let side = Line::new(cell[i], cell[(i + 1) % cell.len()]);
+					LineInterval::line_segment(side)
+						.relate(&LineInterval::line_segment(*edge))
+						.unique_intersection()
+						.is_some()
+				})
+		})
+	}
+}
+
+// Maps coordinates normalized to one bounding rect to coordinates normalized to another, scales are positive, so every point stays on the same side of every line
+struct Transform {
+	scale: (f64, f64),
+	offset: (f64, f64),
+}
+
+impl Transform {
+	fn new(from: &Rect<f64>, to: &Rect<f64>) -> Self {
+		Self {
+			scale: (from.width() / to.width(), from.height() / to.height()),
+			offset: (
+				(from.min().x - to.min().x) / to.width(),
+				(from.min().y - to.min().y) / to.height(),
+			),
+		}
+	}
+
+	fn check(&self, check: &LineSplitCheck) -> LineSplitCheck {
+		let ((sx, sy), (tx, ty)) = (self.scale, self.offset);
+		match *check {
+			LineSplitCheck::MulToX { k, b } => {
+				let k = k * sy / sx;
+				LineSplitCheck::MulToX {
+					k,
+					b: b * sy + ty - k * tx,
+				}
+			},
+			LineSplitCheck::MulToY { k, b } => {
+				let k = k * sx / sy;
+				LineSplitCheck::MulToY {
+					k,
+					b: b * sx + tx - k * ty,
+				}
+			},
+		}
+	}
+
+	fn generalized(&self, check: &LineSplitCheckGeneralized) -> LineSplitCheckGeneralized {
+		match check {
+			LineSplitCheckGeneralized::Less(check) => {
+				LineSplitCheckGeneralized::Less(self.check(check))
+			},
+			LineSplitCheckGeneralized::Greater(check) => {
+				LineSplitCheckGeneralized::Greater(self.check(check))
+			},
+		}
+	}
+
+	fn part(&self, part: &PolygonFastPrecalculatorPart) -> PolygonFastPrecalculatorPart {
+		use PolygonFastPrecalculatorPart::*;
+		match part {
+			LineSplit {
+				check,
+				less,
+				greater,
+			} => LineSplit {
+				check: self.check(check),
+				less: Box::new(self.part(less)),
+				greater: Box::new(self.part(greater)),
+			},
+			Triangle { checks } => Triangle {
+				checks: [
+					self.generalized(&checks[0]),
+					self.generalized(&checks[1]),
+					self.generalized(&checks[2]),
+				],
+			},
+			Edges { edges } => Edges {
+				edges: edges
+					.iter()
+					.map(|edge| CrossingEdge {
+						y_min: edge.y_min * self.scale.1 + self.offset.1,
+						y_max: edge.y_max * self.scale.1 + self.offset.1,
+						check: self.generalized(&edge.check),
+					})
+					.collect(),
+			},
+			None => None,
+		}
+	}
+}
+
+// Cells are counter-clockwise, as the unit square they are cut from
+fn is_in_convex(cell: &[Coordinate<f64>], point: Coordinate<f64>) -> bool {
+	(0..cell.len()).all(|i| {
+		let (a, b) = (cell[i], cell[(i + 1) % cell.len()]);
+		(b.x - a.x) * (point.y - a.y) - (b.y - a.y) * (point.x - a.x) >= 0.
+	})
+}
+
+impl PolygonFastPrecalculator {
+	/// Tree for `polygon` that is an edited version of `previous`, which this tree was calculated for. Parts whose cells are touched by changed edges are calculated as in `calc_with_options`, and when the same split line is chosen again, its subtrees are recalculated in the same way, while subtrees of untouched cells are reused as they are. So when the bounding rect is the same, the tree is the same as from `calc_with_options`, but it's built much faster when only a few vertices are moved. When bounding rect changes, reused subtrees are moved to the new one. When fill rule or precision changes, the tree is calculated from scratch.
+	pub fn recalc(
+		&self,
+		previous: &MultiPolygon<f64>,
+		polygon: MultiPolygon<f64>,
+		options: &CalcOptions,
+	) -> Self {
+		self.recalc_counting(previous, polygon, options).0
+	}
+
+	// Same as `recalc`, also returns count of reused subtrees
+	fn recalc_counting(
+		&self,
+		previous: &MultiPolygon<f64>,
+		polygon: MultiPolygon<f64>,
+		options: &CalcOptions,
+	) -> (Self, usize) {
+		let (mut current, br) = resolve(&polygon, options.fill_rule);
+		let (mut previous, previous_br) = resolve(previous, self.fill_rule);
+		let degenerate = |rect: &Rect<f64>| !(rect.width() > 0. && rect.height() > 0.);
+		if options.fill_rule != self.fill_rule
+			|| options.constants_precision != Precision::F64
+			|| previous_br != self.bounding_rect
+			|| degenerate(&br)
+			|| degenerate(&previous_br)
+		{
+			return (
+				Self::calc_with_options(self.name.clone(), polygon, options),
+				0,
+			);
+		}
+		// Both are moved with the new rect, so unchanged edges stay exactly equal
+		fit_into_default_borders(&mut current, &br);
+		fit_into_default_borders(&mut previous, &br);
+		let parts = Transform::new(&self.bounding_rect, &br).part(&self.parts);
+
+		let previous_edges = edges(&previous).collect::<Vec<_>>();
+		let current_edges = edges(&current).collect::<Vec<_>>();
+		let changes = Changes {
+			previous: &previous,
+			current: &current,
+			edges: previous_edges
+				.iter()
+				.filter(|edge| !current_edges.contains(edge))
+				.chain(
+					current_edges
+						.iter()
+						.filter(|edge| !previous_edges.contains(edge)),
+				)
+				.copied()
+				.collect(),
+		};
+
+		// Lines of the old tree go first, so split lines that are chosen again are snapped to exactly the same checks, and new leaves next to reused ones use the same lines
+		let mut lines = CanonicalLines::default();
+		parts.visit(&mut |part, _| match part {
+			PolygonFastPrecalculatorPart::LineSplit { check, .. } => lines.0.push(*check),
+			PolygonFastPrecalculatorPart::Triangle { checks } => {
+				for check in checks.iter() {
+					match check {
+						LineSplitCheckGeneralized::Less(check)
+						| LineSplitCheckGeneralized::Greater(check) => lines.0.push(*check),
+					}
+				}
+			},
+			PolygonFastPrecalculatorPart::Edges { edges } => {
+				for edge in edges {
+					match edge.check {
+						LineSplitCheckGeneralized::Less(check)
+						| LineSplitCheckGeneralized::Greater(check) => lines.0.push(check),
+					}
+				}
+			},
+			PolygonFastPrecalculatorPart::None => {},
+		});
+		for line in edges(&current) {
+			lines.snap(line);
+		}
+
+		let square = vec![
+			Coordinate { x: 0., y: 0. },
+			Coordinate { x: 1., y: 0. },
+			Coordinate { x: 1., y: 1. },
+			Coordinate { x: 0., y: 1. },
+		];
+		let mut reused = 0;
+		let parts = if current.0.is_empty() {
+			PolygonFastPrecalculatorPart::None
+		} else {
+			recalc_part(
+				&parts,
+				square,
+				current.clone(),
+				&changes,
+				&mut lines,
+				options.max_edges_per_leaf,
+				&mut reused,
+			)
+		};
+		(
+			Self {
+				name: self.name.clone(),
+				bounding_rect: br,
+				fill_rule: self.fill_rule,
+				parts,
+			},
+			reused,
+		)
+	}
+}
+
+fn recalc_part(
+	part: &PolygonFastPrecalculatorPart,
+	cell: Vec<Coordinate<f64>>,
+	polygon: MultiPolygon<f64>,
+	changes: &Changes<'_>,
+	lines: &mut CanonicalLines,
+	max_edges_per_leaf: usize,
+	reused: &mut usize,
+) -> PolygonFastPrecalculatorPart {
+	if !changes.touch(&cell) {
+		*reused += 1;
+		return part.clone();
+	}
+
+	PolygonFastPrecalculatorPart::calc_hybrid_with(
+		polygon,
+		lines,
+		max_edges_per_leaf,
+		&mut |check, less_polygon, greater_polygon, lines| match part {
+			PolygonFastPrecalculatorPart::LineSplit {
+				check: previous,
+				less,
+				greater,
+			} if previous == check => {
+				let less_cell = clip_convex(&cell, &LineSplitCheckGeneralized::Less(*check));
+				let greater_cell = clip_convex(&cell, &LineSplitCheckGeneralized::Greater(*check));
+				(
+					recalc_part(
+						less,
+						less_cell,
+						less_polygon,
+						changes,
+						lines,
+						max_edges_per_leaf,
+						reused,
+					),
+					recalc_part(
+						greater,
+						greater_cell,
+						greater_polygon,
+						changes,
+						lines,
+						max_edges_per_leaf,
+						reused,
+					),
+				)
+			},
+			_ => (
+				PolygonFastPrecalculatorPart::calc_hybrid(less_polygon, lines, max_edges_per_leaf),
+				PolygonFastPrecalculatorPart::calc_hybrid(
+					greater_polygon,
+					lines,
+					max_edges_per_leaf,
+				),
+			),
+		},
+	)
+}
+
+#[cfg(test)]
+mod tests {
+	use geo::{LineString, MultiPolygon, Polygon};
+
+	use crate::{
+		CalcOptions, CanonicalLines, LineSplitCheck, LineSplitCheckGeneralized,
+		PolygonFastPrecalculator, PolygonFastPrecalculatorPart,
+	};
+
+	const COMB: [(f64, f64); 12] = [
+		(0., 0.),
+		(5., 0.),
+		(5., 3.),
+		(4., 3.),
+		(4., 1.),
+		(3., 1.),
+		(3., 3.),
+		(2., 3.),
+		(2., 1.),
+		(1., 1.),
+		(1., 3.),
+		(0., 3.),
+	];
+
+	fn comb(coords: &[(f64, f64)]) -> MultiPolygon<f64> {
+		MultiPolygon(vec![Polygon::new(
+			LineString::from(coords.to_vec()),
+			vec![],
+		)])
+	}
+
+	// Split lines of the old tree can be chosen instead of new lines within `CanonicalLines::EPS`, so constants are compared with it
+	fn assert_same_check(a: &LineSplitCheck, b: &LineSplitCheck) {
+		let close = |a: f64, b: f64| (a - b).abs() < CanonicalLines::EPS;
+		match (a, b) {
+			(LineSplitCheck::MulToX { k: k1, b: b1 }, LineSplitCheck::MulToX { k: k2, b: b2 })
+			| (LineSplitCheck::MulToY { k: k1, b: b1 }, LineSplitCheck::MulToY { k: k2, b: b2 })
+				if close(*k1, *k2) && close(*b1, *b2) => {},
+			_ => panic!("{:?} != {:?}", a, b),
+		}
+	}
+
+	fn assert_same_generalized(a: &LineSplitCheckGeneralized, b: &LineSplitCheckGeneralized) {
+		match (a, b) {
+			(LineSplitCheckGeneralized::Less(a), LineSplitCheckGeneralized::Less(b))
+			| (LineSplitCheckGeneralized::Greater(a), LineSplitCheckGeneralized::Greater(b)) => {
+				assert_same_check(a, b)
+			},
+			_ => panic!("{:?} != {:?}", a, b),
+		}
+	}
+
+	fn assert_same_part(a: &PolygonFastPrecalculatorPart, b: &PolygonFastPrecalculatorPart) {
+		use PolygonFastPrecalculatorPart::*;
+		match (a, b) {
+			(
+				LineSplit {
+					check: a_check,
+					less: a_less,
+					greater: a_greater,
+				},
+				LineSplit {
+					check: b_check,
+					less: b_less,
+					greater: b_greater,
+				},
+			) => {
+				assert_same_check(a_check, b_check);
+				assert_same_part(a_less, b_less);
+				assert_same_part(a_greater, b_greater);
+			},
+			(Triangle { checks: a }, Triangle { checks: b }) => {
+				for (a, b) in a.iter().zip(b.iter()) {
+					assert_same_generalized(a, b);
+				}
+			},
+			(Edges { edges: a }, Edges { edges: b }) if a.len() == b.len() => {
+				for (a, b) in a.iter().zip(b.iter()) {
+					assert!((a.y_min - b.y_min).abs() < CanonicalLines::EPS);
+					assert!((a.y_max - b.y_max).abs() < CanonicalLines::EPS);
+					assert_same_generalized(&a.check, &b.check);
+				}
+			},
+			(None, None) => {},
+			_ => panic!("{:#?}\n!=\n{:#?}", a, b),
+		}
+	}
+
+	// Vertex `index` of the comb is dragged through `path`, every step is recalculated from the previous one
+	fn drag(index: usize, path: &[(f64, f64)]) {
+		for &max_edges_per_leaf in &[0, 8] {
+			let options = CalcOptions {
+				max_edges_per_leaf,
+				..CalcOptions::default()
+			};
+			let mut coords = COMB.to_vec();
+			let mut previous = comb(&coords);
+			let mut tree = PolygonFastPrecalculator::calc_with_options(
+				"comb".to_owned(),
+				previous.clone(),
+				&options,
+			);
+			for &position in path {
+				coords[index] = position;
+				let polygon = comb(&coords);
+				let previous_rect = tree.bounding_rect;
+				tree = tree.recalc(&previous, polygon.clone(), &options);
+
+				let reference = PolygonFastPrecalculator::calc_with_options(
+					"comb".to_owned(),
+					polygon.clone(),
+					&options,
+				);
+				assert_eq!(tree.bounding_rect, reference.bounding_rect);
+				if tree.bounding_rect == previous_rect {
+					assert_same_part(&tree.parts, &reference.parts);
+				} else {
+					// Reused subtrees are moved to the new rect, so they can be split in another way than from scratch
+					let report = tree.verify(&polygon, 2000);
+					assert!(
+						report.is_ok_with_tolerance(1e-9),
+						"{}\n{:?}",
+						report,
+						coords
+					);
+				}
+				previous = polygon;
+			}
+		}
+	}
+
+	#[test]
+	fn moved_inner_vertex() {
+		drag(4, &[(4., 1.5), (4.2, 2.), (3.5, 2.5), (4., 0.5), (4., 1.)]);
+	}
+
+	#[test]
+	fn moved_hull_vertex() {
+		drag(2, &[(5.5, 3.5), (6., 4.), (4.5, 2.5), (5., 3.)]);
+	}
+
+	#[test]
+	fn reuses_untouched_subtrees() {
+		let previous = comb(&COMB);
+		let tree = PolygonFastPrecalculator::calc("comb".to_owned(), previous.clone());
+
+		// Nothing changed, the whole tree is reused
+		let (same, reused) =
+			tree.recalc_counting(&previous, previous.clone(), &CalcOptions::default());
+		assert_eq!(reused, 1);
+		assert_eq!(same.to_string(), tree.to_string());
+
+		// Only the right tooth is moved, subtrees of the left part are reused
+		let mut coords = COMB.to_vec();
+		coords[4] = (4., 1.5);
+		let polygon = comb(&coords);
+		let (moved, reused) =
+			tree.recalc_counting(&previous, polygon.clone(), &CalcOptions::default());
+		assert!(reused > 0);
+		let reference = PolygonFastPrecalculator::calc("comb".to_owned(), polygon);
+		assert_same_part(&moved.parts, &reference.parts);
+
+		// Back to the original comb
+		let back = moved.recalc(&comb(&coords), comb(&COMB), &CalcOptions::default());
+		assert_same_part(&back.parts, &tree.parts);
+	}
+}
@@ -4,15 +4,63 @@ use geo::{
 	line_string,
 	map_coords::MapCoordsInplace,
 	prelude::{Area, BoundingRect, Centroid, EuclideanLength, SimplifyVW},
-	Line, LineString, MultiPolygon, Point, Polygon, Rect,
+	Coordinate, Line, LineString, MultiPolygon, Point, Polygon, Rect,
 };
 use itertools::Itertools;
 use line_intersection::LineInterval;
 use ordered_float::NotNan;
+#[cfg(feature = "serialize")]
+use serde::{Deserialize, Serialize};
 
+#[cfg(feature = "debug-image")]
 use crate::image::PolygonDrawer;
+pub use crate::{
+	binary::BinaryError,
+	cells::Cell,
+	fill_rule::FillRule,
+	grid::GridPrecalculator,
+	parse::{format_rings, parse_multipolygon, parse_rings, ParseError, ParseErrorKind},
+	precision::Precision,
+	reference::{
+		reference_code, reference_edges, reference_uniform_code, Language, ReferenceMethod,
+	},
+	stats::TreeStats,
+	verify::VerificationReport,
+};
 
-#[derive(Clone, Debug, Copy)]
+pub mod binary;
+#[cfg(feature = "bitmap")]
+pub mod bitmap;
+pub mod cells;
+pub mod fill_rule;
+#[cfg(any(feature = "svg", feature = "font"))]
+mod flatten;
+#[cfg(feature = "font")]
+pub mod font;
+#[cfg(feature = "geojson")]
+pub mod geojson;
+#[cfg(test)]
+mod glsl;
+pub mod grid;
+#[cfg(feature = "debug-image")]
+pub mod image;
+pub mod incremental;
+pub mod parse;
+pub mod precision;
+#[cfg(feature = "debug-image")]
+pub mod raster;
+pub mod reference;
+#[cfg(feature = "sdf")]
+pub mod sdf;
+pub mod stats;
+#[cfg(feature = "svg")]
+pub mod svg;
+pub mod verify;
+pub mod visualize;
+pub mod wkt;
+
+#[derive(Clone, Debug, Copy, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
 pub enum LineSplitCheck {
 	MulToX { k: f64, b: f64 },
 	MulToY { k: f64, b: f64 },
@@ -46,7 +94,29 @@ impl LineSplitCheck {
 	}
 }
 
+/// Lines that are already used in the tree. Line that coincides with one of them is replaced by it, so leaves on both sides of a seam use exactly the same constants, and every point of the seam belongs to exactly one of them.
+#[derive(Clone, Debug, Default)]
+pub struct CanonicalLines(pub Vec<LineSplitCheck>);
+
+impl CanonicalLines {
+	const EPS: f64 = 1e-7;
+
+	pub fn snap(&mut self, line: Line<f64>) -> LineSplitCheck {
+		let (start, end) = (line.start_point(), line.end_point());
+		if let Some(check) = self.0.iter().find(|check| {
+			check.less_count(start).1.abs() < Self::EPS && check.less_count(end).1.abs() < Self::EPS
+		}) {
+			return *check;
+		}
+
+		let check = LineSplitCheck::calc(line);
+		self.0.push(check);
+		check
+	}
+}
+
 #[derive(Clone, Debug, Copy)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
 pub enum LineSplitCheckGeneralized {
 	Less(LineSplitCheck),
 	Greater(LineSplitCheck),
@@ -62,7 +132,61 @@ impl LineSplitCheckGeneralized {
 	}
 }
 
+/// Edge of the polygon for the crossing number test, horizontal ray from the point to `+x` crosses it when point is in `[y_min, y_max)` and to the left of the edge.
+#[derive(Clone, Debug, Copy)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct CrossingEdge {
+	pub y_min: f64,
+	pub y_max: f64,
+	/// True to the left of the edge.
+	pub check: LineSplitCheckGeneralized,
+}
+
+impl CrossingEdge {
+	/// `None` for horizontal line, ray never crosses it. Check is always `Less` of `MulToY`, so it has no division by zero.
+	pub fn calc(line: Line<f64>) -> Option<Self> {
+		if line.dy() == 0. {
+			return None;
+		}
+		let k = line.dx() / line.dy();
+		Some(Self {
+			y_min: line.start.y.min(line.end.y),
+			y_max: line.start.y.max(line.end.y),
+			check: LineSplitCheckGeneralized::Less(LineSplitCheck::MulToY {
+				k,
+				b: line.start.x - k * line.start.y,
+			}),
+		})
+	}
+
+	/// Same as `calc`, but the line is snapped to `lines`, so edge that lies on a split line of the tree uses exactly its check, as triangles do.
+	pub fn snapped(line: Line<f64>, lines: &mut CanonicalLines) -> Option<Self> {
+		if line.dy() == 0. {
+			return None;
+		}
+		let check = lines.snap(line);
+		// Point to the left has `x` less by 1 than the point on the line, so it's less than `MulToX` line only when `k < 0`
+		let check = match check {
+			LineSplitCheck::MulToY { .. } => LineSplitCheckGeneralized::Less(check),
+			LineSplitCheck::MulToX { k, .. } if k < 0. => LineSplitCheckGeneralized::Less(check),
+			LineSplitCheck::MulToX { k, .. } if k > 0. => LineSplitCheckGeneralized::Greater(check),
+			// Snapped to a horizontal line
+			LineSplitCheck::MulToX { .. } => return None,
+		};
+		Some(Self {
+			y_min: line.start.y.min(line.end.y),
+			y_max: line.start.y.max(line.end.y),
+			check,
+		})
+	}
+
+	pub fn is_crossed(&self, point: Point<f64>) -> bool {
+		self.y_min <= point.y() && point.y() < self.y_max && self.check.check(point)
+	}
+}
+
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
 pub enum PolygonFastPrecalculatorPart {
 	LineSplit {
 		check: LineSplitCheck,
@@ -72,13 +196,62 @@ pub enum PolygonFastPrecalculatorPart {
 	Triangle {
 		checks: [LineSplitCheckGeneralized; 3],
 	},
+	/// Part of the polygon in the cell, point is inside when it crosses odd count of edges. It's cheaper than a subtree for small parts with several edges.
+	Edges {
+		edges: Vec<CrossingEdge>,
+	},
 	None,
 }
 
 // static mut counter: i32 = 0;
 
 impl PolygonFastPrecalculatorPart {
-	pub fn calc(mut polygon: MultiPolygon<f64>) -> Self {
+	const CHECK_COST: f64 = 1.;
+	// Divergent branches are much more expensive on GPU than arithmetic without them
+	const SPLIT_COST: f64 = 2.;
+
+	pub fn calc(polygon: MultiPolygon<f64>) -> Self {
+		Self::calc_with_lines(polygon, &mut CanonicalLines::default())
+	}
+
+	pub fn calc_with_lines(polygon: MultiPolygon<f64>, lines: &mut CanonicalLines) -> Self {
+		Self::calc_hybrid(polygon, lines, 0)
+	}
+
+	/// Same as `calc_with_lines`, but subtree is replaced by `Edges` leaf when the part of the polygon has at most `max_edges_per_leaf` edges and checking them is cheaper by `cost`.
+	pub fn calc_hybrid(
+		polygon: MultiPolygon<f64>,
+		lines: &mut CanonicalLines,
+		max_edges_per_leaf: usize,
+	) -> Self {
+		Self::calc_hybrid_with(
+			polygon,
+			lines,
+			max_edges_per_leaf,
+			&mut |_, less, greater, lines| {
+				(
+					Self::calc_hybrid(less, lines, max_edges_per_leaf),
+					Self::calc_hybrid(greater, lines, max_edges_per_leaf),
+				)
+			},
+		)
+	}
+
+	// Same as `calc_hybrid`, but both sides of the chosen split are calculated by `children`, so `recalc` can reuse old subtrees there
+	pub(crate) fn calc_hybrid_with(
+		mut polygon: MultiPolygon<f64>,
+		lines: &mut CanonicalLines,
+		max_edges_per_leaf: usize,
+		children: &mut impl FnMut(
+			&LineSplitCheck,
+			MultiPolygon<f64>,
+			MultiPolygon<f64>,
+			&mut CanonicalLines,
+		) -> (Self, Self),
+	) -> Self {
+		// Simplification and order of checks depend on the start of rings, and Clipper starts them anywhere
+		polygon = canonicalize(&polygon);
+
 		// Simplify figure
 		polygon = polygon.simplifyvw(&0.0001);
 
@@ -106,7 +279,10 @@ impl PolygonFastPrecalculatorPart {
 		}
 
 		// This is triangle
-		if polygon.0.len() == 1 && polygon.0[0].exterior().points_iter().count() == 4 {
+		if polygon.0.len() == 1
+			&& polygon.0[0].exterior().points_iter().count() == 4
+			&& polygon.0[0].interiors().is_empty()
+		{
 			let center = polygon.centroid().unwrap();
 
 			let checks = polygon
@@ -114,7 +290,7 @@ impl PolygonFastPrecalculatorPart {
 				.iter()
 				.map(|poly| poly.exterior().lines())
 				.flatten()
-				.map(|line| LineSplitCheck::calc(line))
+				.map(|line| lines.snap(line))
 				.map(|check| {
 					if check.is_less(center) {
 						LineSplitCheckGeneralized::Less(check)
@@ -142,16 +318,13 @@ impl PolygonFastPrecalculatorPart {
 		));
 		let br = br.to_polygon();
 
-		let all_points = polygon
-			.0
-			.iter()
-			.map(|poly| poly.exterior().points_iter())
-			.flatten()
-			.collect::<Vec<_>>();
+		let all_points = points_iter(&polygon).collect::<Vec<_>>();
 
+		// Line in the other direction gives the same check, so only one direction is taken
 		let all_lines = all_points
 			.iter()
 			.cartesian_product(all_points.iter())
+			.filter(|(start, end)| point_key(**start) < point_key(**end))
 			.map(|(start, end)| Line::new(*start, *end));
 
 		// For all lines in current figure, find best line that cut current polygons into 2 equivalent figures.
@@ -233,16 +406,8 @@ impl PolygonFastPrecalculatorPart {
 			})
 			.filter_map(|(line, result1, result2)| {
 				// Metric by points count (works good)
-				let a1 = result1
-					.iter()
-					.map(|poly| poly.exterior().points_iter())
-					.flatten()
-					.count() as f64;
-				let a2 = result2
-					.iter()
-					.map(|poly| poly.exterior().points_iter())
-					.flatten()
-					.count() as f64;
+				let a1 = points_iter(&result1).count() as f64;
+				let a2 = points_iter(&result2).count() as f64;
 
 				// Metric by area (works bad)
 				// let a1 = result1.unsigned_area();
@@ -260,16 +425,25 @@ impl PolygonFastPrecalculatorPart {
 					}
 				}
 			})
-			.min_by_key(|(_, _, _, val)| {
-				NotNan::new(*val)
-					.unwrap_or_else(|_| panic!("can't find delimiter line:\n{:#?}", polygon))
+			// Ties are broken by the line itself, so the result doesn't depend on order of points
+			.min_by_key(|(line, _, _, val)| {
+				let val = NotNan::new(*val)
+					.unwrap_or_else(|_| panic!("can't find delimiter line:\n{:#?}", polygon));
+				(
+					val,
+					point_key(line.start_point()),
+					point_key(line.end_point()),
+				)
 			});
 
 		let mut best = best.unwrap_or_else(|| {
 			// For debug
-			let mut image = PolygonDrawer::new(1000);
-			image.add_multipolygon(polygon.clone(), (0, 0, 0));
-			image.draw_and_save("panic_result.png");
+			#[cfg(feature = "debug-image")]
+			{
+				let mut image = PolygonDrawer::new(1000);
+				image.add_multipolygon(polygon.clone(), (0, 0, 0));
+				image.draw_and_save("panic_result.png");
+			}
 			panic!("can't find delimiter line:\n{:#?}", polygon)
 		});
 
@@ -290,7 +464,7 @@ impl PolygonFastPrecalculatorPart {
 		};
 		*/
 
-		let check = LineSplitCheck::calc(best.0);
+		let check = lines.snap(best.0);
 
 		// Find most far pont and check if this point is less than provided check, i.e. check is this polygon really lies on `less` part of this check
 		let should_swap = best
@@ -308,33 +482,113 @@ impl PolygonFastPrecalculatorPart {
 			std::mem::swap(&mut best.1, &mut best.2);
 		}
 
-		Self::LineSplit {
+		let (less, greater) = children(&check, best.1, best.2, lines);
+		let result = Self::LineSplit {
 			check,
-			less: Box::new(Self::calc(best.1)),
-			greater: Box::new(Self::calc(best.2)),
+			less: Box::new(less),
+			greater: Box::new(greater),
+		};
+
+		// Children are already the cheapest, so only this level is compared
+		if max_edges_per_leaf > 0 {
+			// Lines of edges are kept only when the leaf is taken
+			let mut edge_lines = lines.clone();
+			let edges = edges(&polygon)
+				.filter_map(|line| CrossingEdge::snapped(line, &mut edge_lines))
+				.collect::<Vec<_>>();
+			if edges.len() <= max_edges_per_leaf {
+				let leaf = Self::Edges { edges };
+				if leaf.cost() < result.cost() {
+					*lines = edge_lines;
+					return leaf;
+				}
+			}
 		}
+		result
 	}
+
+	/// Estimated count of line checks to evaluate this part, both sides of a split are taken as equally probable.
+	pub fn cost(&self) -> f64 {
+		use PolygonFastPrecalculatorPart::*;
+		match self {
+			LineSplit { less, greater, .. } => {
+				Self::SPLIT_COST + (less.cost() + greater.cost()) / 2.
+			},
+			Triangle { .. } => 3. * Self::CHECK_COST,
+			// Every edge has two comparisons of `y` and one of `x`, as many as a triangle
+			Edges { edges } => 3. * edges.len() as f64 * Self::CHECK_COST,
+			None => 0.,
+		}
+	}
+
+	/// Calls `f` for this part and all nested parts with their depth, parent before children.
+	pub fn visit<'a>(&'a self, f: &mut impl FnMut(&'a PolygonFastPrecalculatorPart, usize)) {
+		fn visit_inner<'a>(
+			part: &'a PolygonFastPrecalculatorPart,
+			depth: usize,
+			f: &mut impl FnMut(&'a PolygonFastPrecalculatorPart, usize),
+		) {
+			f(part, depth);
+			if let PolygonFastPrecalculatorPart::LineSplit { less, greater, .. } = part {
+				visit_inner(less, depth + 1, f);
+				visit_inner(greater, depth + 1, f);
+			}
+		}
+
+		visit_inner(self, 0, f);
+	}
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct CalcOptions {
+	pub fill_rule: FillRule,
+	/// Round constants of generated code to this precision, see `PolygonFastPrecalculator::round_constants`.
+	pub constants_precision: Precision,
+	/// Parts of the polygon with at most this count of edges can be checked by crossing number instead of a subtree, `0` disables it.
+	pub max_edges_per_leaf: usize,
 }
 
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
 pub struct PolygonFastPrecalculator {
 	pub name: String,
 	pub bounding_rect: Rect<f64>,
+	pub fill_rule: FillRule,
 	pub parts: PolygonFastPrecalculatorPart,
 }
 
 impl PolygonFastPrecalculator {
-	pub fn calc(name: String, mut polygon: MultiPolygon<f64>) -> Self {
-		let br = polygon.bounding_rect().unwrap();
-		polygon.map_coords_inplace(|&(x, y)| {
-			let r = fit_point_into_default_borders(Point::new(x, y), &br);
-			(r.x(), r.y())
-		});
-		Self {
+	/// Result depends only on the shape of polygon, not on the order of its vertices, rings and polygons, and is the same on every run.
+	pub fn calc(name: String, polygon: MultiPolygon<f64>) -> Self {
+		Self::calc_with_options(name, polygon, &CalcOptions::default())
+	}
+
+	pub fn calc_with_options(
+		name: String,
+		polygon: MultiPolygon<f64>,
+		options: &CalcOptions,
+	) -> Self {
+		let (polygon, br) = normalize(&polygon, options.fill_rule);
+		let mut lines = edge_lines(&polygon);
+
+		let mut result = Self {
 			name,
 			bounding_rect: br,
-			parts: PolygonFastPrecalculatorPart::calc(polygon),
+			fill_rule: options.fill_rule,
+			parts: if polygon.0.is_empty() {
+				PolygonFastPrecalculatorPart::None
+			} else {
+				PolygonFastPrecalculatorPart::calc_hybrid(
+					polygon,
+					&mut lines,
+					options.max_edges_per_leaf,
+				)
+			},
+		};
+		if options.constants_precision != Precision::F64 {
+			result.round_constants(options.constants_precision);
 		}
+		result
 	}
 
 	pub fn is_inside(&self, mut point: Point<f64>) -> bool {
@@ -353,6 +607,7 @@ impl PolygonFastPrecalculator {
 					}
 				},
 				Triangle { checks } => checks.iter().all(|c| c.check(point)),
+				Edges { edges } => edges.iter().filter(|e| e.is_crossed(point)).count() % 2 == 1,
 				None => return false,
 			}
 		}
@@ -379,16 +634,57 @@ pub fn fit_point_into_default_borders(
 	point
 }
 
+// Constants that are exact in `f32` are printed in the shortest form, GPU reads them as `f32` anyway
+pub(crate) struct Constant(pub f64);
+
+impl fmt::Display for Constant {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		if self.0 as f32 as f64 == self.0 {
+			write!(f, "{:e}", self.0 as f32)
+		} else {
+			write!(f, "{:e}", self.0)
+		}
+	}
+}
+
+// Moves the bounding rect to `[0, 1]²`, every generated function starts with it
+pub(crate) struct Normalization<'a>(pub &'a Rect<f64>);
+
+impl fmt::Display for Normalization<'_> {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(
+			f,
+			"a = (a - vec2({}, {})) / vec2({}, {});",
+			Constant(self.0.min().x),
+			Constant(self.0.min().y),
+			Constant(self.0.width()),
+			Constant(self.0.height())
+		)
+	}
+}
+
 impl fmt::Display for LineSplitCheck {
 	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
 		use LineSplitCheck::*;
 		match self {
-			MulToX { k, b } => write!(f, "a.y < a.x * {:e} + ({:e})", k, b),
-			MulToY { k, b } => write!(f, "a.x < a.y * {:e} + ({:e})", k, b),
+			MulToX { k, b } => write!(f, "a.y < a.x * {} + ({})", Constant(*k), Constant(*b)),
+			MulToY { k, b } => write!(f, "a.x < a.y * {} + ({})", Constant(*k), Constant(*b)),
 		}
 	}
 }
 
+impl fmt::Display for CrossingEdge {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(
+			f,
+			"({} <= a.y && a.y < {} && {})",
+			Constant(self.y_min),
+			Constant(self.y_max),
+			self.check
+		)
+	}
+}
+
 impl fmt::Display for LineSplitCheckGeneralized {
 	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
 		use LineSplitCheckGeneralized::*;
@@ -443,6 +739,13 @@ impl fmt::Display for PolygonFastPrecalculator {
 				Triangle { checks } => {
 					out!("return {} && {} && {};", checks[0], checks[1], checks[2]);
 				},
+				// Parity of crossed edges
+				Edges { edges } if edges.is_empty() => {
+					out!("return false;");
+				},
+				Edges { edges } => {
+					out!("return {};", edges.iter().join(" != "));
+				},
 				None => {
 					out!("return false;");
 				},
@@ -459,7 +762,7 @@ impl fmt::Display for PolygonFastPrecalculator {
 
 		out!("bool is_inside_{}(vec2 a) {{", self.name);
 		inner! {
-			out!("a = (a - vec2({:e}, {:e})) / vec2({:e}, {:e});", self.bounding_rect.min().x, self.bounding_rect.min().y, self.bounding_rect.width(), self.bounding_rect.height());
+			out!("{}", Normalization(&self.bounding_rect));
 			out!("if (0. <= a.x && a.x <= 1. && 0. <= a.y && a.y <= 1.) {{");
 			inner! {
 				print_inner(&self.parts, deep, f)?;
@@ -476,154 +779,321 @@ impl fmt::Display for PolygonFastPrecalculator {
 	}
 }
 
-pub fn vec_to_multipolygon(array: Vec<(f64, f64)>) -> MultiPolygon<f64> {
-	MultiPolygon::from(vec![Polygon::new(LineString::from(array), vec![])])
+pub(crate) fn points_iter(polygon: &MultiPolygon<f64>) -> impl Iterator<Item = Point<f64>> + '_ {
+	polygon
+		.0
+		.iter()
+		.map(|poly| {
+			std::iter::once(poly.exterior())
+				.chain(poly.interiors().iter())
+				.map(|ring| ring.points_iter())
+				.flatten()
+		})
+		.flatten()
 }
 
-pub(crate) fn mymax(a: f64, b: f64) -> f64 {
-	if a > b { a } else { b }
+/// Groups rings into polygons by nesting: ring that lies inside of even count of other rings is an exterior, inside of odd count is a hole of the smallest exterior around it.
+pub fn rings_to_multipolygon(mut rings: Vec<LineString<f64>>) -> MultiPolygon<f64> {
+	use crate::fill_rule::{ring_signed_area, ring_winding_number};
+
+	rings.retain(|ring| ring.0.len() >= 3);
+	rings.sort_by(|a, b| {
+		ring_signed_area(b)
+			.abs()
+			.partial_cmp(&ring_signed_area(a).abs())
+			.unwrap_or(std::cmp::Ordering::Equal)
+	});
+
+	let mut result: Vec<Polygon<f64>> = Vec::new();
+	for ring in rings {
+		let point = Point(ring.0[0]);
+		let depth = result
+			.iter()
+			.map(|poly| std::iter::once(poly.exterior()).chain(poly.interiors().iter()))
+			.flatten()
+			.filter(|other| ring_winding_number(other, point) != 0)
+			.count();
+		if depth % 2 == 0 {
+			result.push(Polygon::new(ring, vec![]));
+		} else if let Some(parent) = result
+			.iter_mut()
+			.rev()
+			.find(|poly| ring_winding_number(poly.exterior(), point) != 0)
+		{
+			parent.interiors_push(ring);
+		}
+	}
+	MultiPolygon(result)
 }
 
-pub(crate) fn mymin(a: f64, b: f64) -> f64 {
-	if a < b { a } else { b }
+// Resolves self-intersections and moves polygon into `[0, 1]²`, returns its original bounding rect
+pub(crate) fn normalize(
+	polygon: &MultiPolygon<f64>,
+	fill_rule: FillRule,
+) -> (MultiPolygon<f64>, Rect<f64>) {
+	let (mut resolved, br) = resolve(polygon, fill_rule);
+	fit_into_default_borders(&mut resolved, &br);
+	(resolved, br)
 }
 
-// For debug
-pub(crate) mod image {
-	use std::{fs::File, io::BufWriter, path::Path};
+// Same as `normalize`, but the polygon is not moved
+pub(crate) fn resolve(
+	polygon: &MultiPolygon<f64>,
+	fill_rule: FillRule,
+) -> (MultiPolygon<f64>, Rect<f64>) {
+	// Self-intersections are resolved here, so clipping never depends on Clipper's own fill rule
+	let resolved = canonicalize(&fill_rule.resolve(polygon));
+	let br = match resolved.bounding_rect() {
+		Some(br) => br,
+		// Nothing is filled, e.g. counter-clockwise ring with `Negative` rule, the original rect is kept for the generated code
+		None => polygon
+			.bounding_rect()
+			.unwrap_or_else(|| Rect::new(Coordinate { x: 0., y: 0. }, Coordinate { x: 1., y: 1. })),
+	};
+	(resolved, br)
+}
 
-	use geo::{prelude::*, Coordinate, MultiPolygon, Point, Polygon};
-	use glam::Vec2;
+pub(crate) fn fit_into_default_borders(polygon: &mut MultiPolygon<f64>, br: &Rect<f64>) {
+	polygon.map_coords_inplace(|&(x, y)| {
+		let r = fit_point_into_default_borders(Point::new(x, y), br);
+		(r.x(), r.y())
+	});
+}
 
-	use crate::{mymax, mymin};
+pub(crate) fn edges(polygon: &MultiPolygon<f64>) -> impl Iterator<Item = Line<f64>> + '_ {
+	polygon
+		.0
+		.iter()
+		.map(|poly| std::iter::once(poly.exterior()).chain(poly.interiors().iter()))
+		.flatten()
+		.map(|ring| ring.lines())
+		.flatten()
+		.filter(|line| line.dx() != 0. || line.dy() != 0.)
+}
 
-	pub struct ImageIterator {
-		x: usize,
-		y: usize,
-		w: usize,
-		h: usize,
+// Edges of polygon go first, so leaf edges lying on them use the original line instead of line recomputed from clipped points
+pub(crate) fn edge_lines(polygon: &MultiPolygon<f64>) -> CanonicalLines {
+	let mut lines = CanonicalLines::default();
+	for line in edges(polygon) {
+		lines.snap(line);
 	}
+	lines
+}
 
-	impl Iterator for ImageIterator {
-		type Item = (usize, usize, Vec2);
-
-		fn next(&mut self) -> Option<Self::Item> {
-			if self.y == self.h {
-				return None;
-			}
-
-			let min = std::cmp::min(self.w, self.h) as f32;
-			let to_return = (
-				self.x,
-				self.y,
-				(Vec2::new(self.x as f32, self.y as f32) / min * 2. - Vec2::new(1., 1.)),
-			);
+/// Same polygon with canonical order of vertices: exteriors are counter-clockwise and holes are clockwise, every ring starts from its lexicographically smallest vertex, polygons and holes are sorted by their rings. Polygons that differ only in order of vertices give byte-identical code.
+pub fn canonicalize(polygon: &MultiPolygon<f64>) -> MultiPolygon<f64> {
+	use crate::fill_rule::ring_signed_area;
 
-			self.x += 1;
-			if self.x == self.w {
-				self.y += 1;
-				self.x = 0;
-			}
-			Some(to_return)
+	fn canonical_ring(ring: &LineString<f64>, counter_clockwise: bool) -> LineString<f64> {
+		let mut coords = ring.0.clone();
+		if coords.len() > 1 && coords.first() == coords.last() {
+			coords.pop();
+		}
+		if (ring_signed_area(ring) > 0.) != counter_clockwise {
+			coords.reverse();
+		}
+		if let Some(start) = (0..coords.len()).min_by_key(|i| point_key(Point(coords[*i]))) {
+			coords.rotate_left(start);
+		}
+		if let Some(first) = coords.first().copied() {
+			coords.push(first);
 		}
+		LineString(coords)
 	}
 
-	pub struct Image {
-		w: usize,
-		h: usize,
-		data: Vec<u8>,
+	fn ring_key(ring: &LineString<f64>) -> Vec<(NotNan<f64>, NotNan<f64>)> {
+		ring.points_iter().map(point_key).collect()
 	}
 
-	impl Image {
-		pub fn new(w: usize, h: usize) -> Self {
-			Self {
-				w,
-				h,
-				data: vec![0; w * h * 3],
-			}
-		}
+	let mut result = polygon
+		.0
+		.iter()
+		.map(|poly| {
+			let mut interiors = poly
+				.interiors()
+				.iter()
+				.map(|ring| canonical_ring(ring, false))
+				.collect::<Vec<_>>();
+			interiors.sort_by_key(ring_key);
+			Polygon::new(canonical_ring(poly.exterior(), true), interiors)
+		})
+		.collect::<Vec<_>>();
+	result.sort_by_key(|poly| ring_key(poly.exterior()));
+	MultiPolygon(result)
+}
 
-		pub fn iter(&self) -> ImageIterator {
-			ImageIterator {
-				x: 0,
-				y: 0,
-				w: self.w,
-				h: self.h,
-			}
-		}
+// Total order of points, NaN never appears in normalized polygons
+fn point_key(point: Point<f64>) -> (NotNan<f64>, NotNan<f64>) {
+	(
+		NotNan::new(point.x()).unwrap(),
+		NotNan::new(point.y()).unwrap(),
+	)
+}
 
-		pub fn set_pixel(&mut self, x: usize, y: usize, color: (u8, u8, u8)) {
-			let offset = (x + y * self.w) * 3;
-			self.data[offset + 0] = color.0;
-			self.data[offset + 1] = color.1;
-			self.data[offset + 2] = color.2;
-		}
+pub fn vec_to_multipolygon(array: Vec<(f64, f64)>) -> MultiPolygon<f64> {
+	MultiPolygon::from(vec![Polygon::new(LineString::from(array), vec![])])
+}
+
+pub(crate) fn mymax(a: f64, b: f64) -> f64 {
+	if a > b { a } else { b }
+}
 
-		pub fn save(&self, filename: &str) {
-			let path = Path::new(filename);
-			let file = File::create(path).unwrap();
-			let ref mut wr = BufWriter::new(file);
+#[cfg(feature = "debug-image")]
+pub(crate) fn mymin(a: f64, b: f64) -> f64 {
+	if a < b { a } else { b }
+}
 
-			let mut encoder = png::Encoder::new(wr, self.w as u32, self.h as u32);
-			encoder.set_color(png::ColorType::RGB);
-			encoder.set_depth(png::BitDepth::Eight);
-			let mut writer = encoder.write_header().unwrap();
+#[cfg(test)]
+mod tests {
+	use geo::{LineString, MultiPolygon, Polygon};
 
-			writer.write_image_data(&self.data).unwrap();
+	use crate::{
+		edges, CalcOptions, CanonicalLines, CrossingEdge, PolygonFastPrecalculator,
+		PolygonFastPrecalculatorPart,
+	};
+
+	fn ring(points: &[(f64, f64)]) -> LineString<f64> {
+		LineString::from(points.to_vec())
+	}
+
+	// Closing vertex is dropped and appended again after the change
+	fn change_ring(ring: &LineString<f64>, rotate: usize, reverse: bool) -> LineString<f64> {
+		let mut coords = ring.0[..ring.0.len() - 1].to_vec();
+		let len = coords.len();
+		coords.rotate_left(rotate % len);
+		if reverse {
+			coords.reverse();
 		}
+		coords.push(coords[0]);
+		LineString(coords)
 	}
 
-	pub struct PolygonDrawer {
-		image: Image,
-		polygons: Vec<(MultiPolygon<f64>, (u8, u8, u8))>,
+	fn change(
+		polygon: &MultiPolygon<f64>,
+		rotate: usize,
+		reverse: bool,
+		reorder: bool,
+	) -> MultiPolygon<f64> {
+		let mut polygons = polygon
+			.0
+			.iter()
+			.map(|poly| {
+				let mut interiors = poly
+					.interiors()
+					.iter()
+					.map(|hole| change_ring(hole, rotate, reverse))
+					.collect::<Vec<_>>();
+				if reorder {
+					interiors.reverse();
+				}
+				Polygon::new(change_ring(poly.exterior(), rotate, reverse), interiors)
+			})
+			.collect::<Vec<_>>();
+		if reorder {
+			polygons.reverse();
+		}
+		MultiPolygon(polygons)
 	}
 
-	impl PolygonDrawer {
-		pub fn new(size: usize) -> Self {
-			Self {
-				image: Image::new(size + 20, size + 20),
-				polygons: vec![],
+	#[test]
+	fn code_does_not_depend_on_vertex_order() {
+		let polygon = MultiPolygon(vec![
+			Polygon::new(ring(&[(0., 0.), (6., 0.), (6., 4.), (0., 4.)]), vec![
+				ring(&[(1., 1.), (1., 3.), (2., 3.), (2., 1.)]),
+				ring(&[(3., 1.), (3., 2.), (5., 3.), (5., 1.)]),
+			]),
+			Polygon::new(
+				ring(&[(7., 0.5), (9., 1.), (8.5, 3.), (7.5, 3.5), (7.2, 2.)]),
+				vec![],
+			),
+		]);
+
+		for &max_edges_per_leaf in &[0, 8] {
+			let options = CalcOptions {
+				max_edges_per_leaf,
+				..CalcOptions::default()
+			};
+			let code = |polygon: MultiPolygon<f64>| {
+				PolygonFastPrecalculator::calc_with_options("test".to_owned(), polygon, &options)
+					.to_string()
+			};
+			let expected = code(polygon.clone());
+			for &rotate in &[0, 1, 3] {
+				for &reverse in &[false, true] {
+					for &reorder in &[false, true] {
+						let changed = change(&polygon, rotate, reverse, reorder);
+						assert_eq!(
+							code(changed),
+							expected,
+							"rotate {}, reverse {}, reorder {}",
+							rotate,
+							reverse,
+							reorder
+						);
+					}
+				}
 			}
 		}
+	}
 
-		pub fn add_polygon(&mut self, polygon: Polygon<f64>, color: (u8, u8, u8)) {
-			self.polygons.push((MultiPolygon::from(polygon), color));
-		}
+	// Count of `Triangle` and `Edges` leaves
+	fn leaves(part: &PolygonFastPrecalculatorPart) -> (usize, usize) {
+		let mut result = (0, 0);
+		part.visit(&mut |part, _| match part {
+			PolygonFastPrecalculatorPart::Triangle { .. } => result.0 += 1,
+			PolygonFastPrecalculatorPart::Edges { .. } => result.1 += 1,
+			_ => {},
+		});
+		result
+	}
 
-		pub fn add_multipolygon(&mut self, polygon: MultiPolygon<f64>, color: (u8, u8, u8)) {
-			self.polygons.push((polygon, color));
+	fn edges_leaf(polygon: &MultiPolygon<f64>) -> PolygonFastPrecalculatorPart {
+		let mut lines = CanonicalLines::default();
+		PolygonFastPrecalculatorPart::Edges {
+			edges: edges(polygon)
+				.filter_map(|line| CrossingEdge::snapped(line, &mut lines))
+				.collect(),
 		}
+	}
 
-		pub fn draw_and_save(&mut self, filename: &str) {
-			let mut rect = self.polygons[0].0.bounding_rect().unwrap();
-			for current in self
-				.polygons
-				.iter()
-				.filter_map(|(poly, _)| poly.bounding_rect())
-			{
-				let minx = mymin(current.min().x, rect.min().x);
-				let miny = mymin(current.min().y, rect.min().y);
-				rect.set_min(Coordinate::from((minx, miny)));
-
-				let maxx = mymax(current.max().x, rect.max().x);
-				let maxy = mymax(current.max().y, rect.max().y);
-				rect.set_max(Coordinate::from((maxx, maxy)));
-			}
-			for (x, y, _) in self.image.iter() {
-				let point = Point::new(
-					(x as f64 - 10.) / (self.image.w - 20) as f64 * rect.width(),
-					(y as f64 - 10.) / (self.image.w - 20) as f64 * rect.height(),
-				) + rect.min().into();
-
-				self.image.set_pixel(x, y, (255, 255, 255));
-				for (poly, color) in self.polygons.iter() {
-					if poly.contains(&point) {
-						self.image.set_pixel(x, y, *color);
-						break;
-					}
-				}
-			}
-			self.image.save(filename);
+	#[test]
+	fn leaf_kind_by_cost() {
+		// No edge is horizontal, so the leaf has all 4 of them
+		let quad = MultiPolygon(vec![Polygon::new(
+			ring(&[(0., 0.), (4., 1.), (3., 4.), (-1., 3.)]),
+			vec![],
+		)]);
+		let comb = MultiPolygon(vec![Polygon::new(
+			ring(&[
+				(0., 0.),
+				(5., 0.),
+				(5., 3.),
+				(4., 3.),
+				(4., 1.),
+				(3., 1.),
+				(3., 3.),
+				(2., 3.),
+				(2., 1.),
+				(1., 1.),
+				(1., 3.),
+				(0., 3.),
+			]),
+			vec![],
+		)]);
+		assert_eq!(edges_leaf(&quad).cost(), 12.);
+		assert_eq!(edges_leaf(&comb).cost(), 18.);
+
+		for polygon in &[quad, comb] {
+			let tree = PolygonFastPrecalculatorPart::calc_hybrid(
+				polygon.clone(),
+				&mut CanonicalLines::default(),
+				16,
+			);
+			// Split into triangles costs less than checking every edge as often as a triangle
+			let (triangles, edges) = leaves(&tree);
+			assert!(triangles >= 2, "{:?}", tree);
+			assert_eq!(edges, 0, "{:?}", tree);
+			assert!(tree.cost() < edges_leaf(polygon).cost());
 		}
 	}
 }
@@ -0,0 +1,275 @@
+use std::fmt;
+
+use geo::{Coordinate, LineString, MultiPolygon};
+
+use crate::rings_to_multipolygon;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ParseErrorKind {
+	InvalidNumber(String),
+	MissingCoordinate,
+	ExtraToken(String),
+	/// Ring that starts on this line has less than 3 points.
+	TooFewPoints,
+	NoPoints,
+}
+
+/// Error with 1-based line and column in the parsed text.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ParseError {
+	pub line: usize,
+	pub column: usize,
+	pub kind: ParseErrorKind,
+}
+
+impl fmt::Display for ParseError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		use ParseErrorKind::*;
+		write!(f, "{}:{}: ", self.line, self.column)?;
+		match &self.kind {
+			InvalidNumber(token) => write!(f, "can't parse number `{}`", token),
+			MissingCoordinate => write!(f, "expected two numbers, found one"),
+			ExtraToken(token) => write!(f, "there is something after two numbers: `{}`", token),
+			TooFewPoints => write!(f, "ring should have at least 3 points"),
+			NoPoints => write!(f, "there is no points"),
+		}
+	}
+}
+
+impl std::error::Error for ParseError {}
+
+/// Parses points `x y` or `x, y`, one point per line. Text after `#` or `//` is a comment. Empty line starts a new ring, rings are grouped into polygons with holes by nesting, see `rings_to_multipolygon`.
+pub fn parse_multipolygon(s: &str) -> Result<MultiPolygon<f64>, ParseError> {
+	Ok(rings_to_multipolygon(parse_rings(s)?))
+}
+
+/// Rings of `parse_multipolygon` in the order of the text, every ring has at least 3 points.
+pub fn parse_rings(s: &str) -> Result<Vec<LineString<f64>>, ParseError> {
+	let mut rings: Vec<(usize, Vec<Coordinate<f64>>)> = Vec::new();
+	let mut current: Option<(usize, Vec<Coordinate<f64>>)> = None;
+
+	for (line_index, line) in s.lines().enumerate() {
+		let line_number = line_index + 1;
+		let comment = [line.find('#'), line.find("//")]
+			.iter()
+			.filter_map(|x| *x)
+			.min();
+		let content = &line[..comment.unwrap_or_else(|| line.len())];
+
+		let mut tokens = tokens(content);
+		let (x_column, x) = match tokens.next() {
+			Some(token) => token,
+			None => {
+				if comment.is_none() {
+					rings.extend(current.take());
+				}
+				continue;
+			},
+		};
+		let (y_column, y) = tokens.next().ok_or(ParseError {
+			line: line_number,
+			column: content.trim_end().chars().count() + 1,
+			kind: ParseErrorKind::MissingCoordinate,
+		})?;
+		if let Some((column, token)) = tokens.next() {
+			return Err(ParseError {
+				line: line_number,
+				column,
+				kind: ParseErrorKind::ExtraToken(token.to_owned()),
+			});
+		}
+
+		// `f64::from_str` also accepts "NaN" and "inf", they can't be vertices
+		let number = |column: usize, token: &str| {
+			token
+				.parse::<f64>()
+				.ok()
+				.filter(|x| x.is_finite())
+				.ok_or_else(|| ParseError {
+					line: line_number,
+					column,
+					kind: ParseErrorKind::InvalidNumber(token.to_owned()),
+				})
+		};
+		let coord = Coordinate {
+			x: number(x_column, x)?,
+			y: number(y_column, y)?,
+		};
+
+		current
+			.get_or_insert_with(|| (line_number, Vec::new()))
+			.1
+			.push(coord);
+	}
+	rings.extend(current.take());
+
+	if rings.is_empty() {
+		return Err(ParseError {
+			line: 1,
+			column: 1,
+			kind: ParseErrorKind::NoPoints,
+		});
+	}
+
+	rings
+		.into_iter()
+		.map(|(line, ring)| {
+			if ring.len() < 3 {
+				Err(ParseError {
+					line,
+					column: 1,
+					kind: ParseErrorKind::TooFewPoints,
+				})
+			} else {
+				Ok(LineString(ring))
+			}
+		})
+		.collect()
+}
+
+/// Writes rings in the format of `parse_rings`, rings are separated by empty lines.
+pub fn format_rings(rings: &[LineString<f64>]) -> String {
+	rings
+		.iter()
+		.map(|ring| {
+			ring.0
+				.iter()
+				.map(|coord| format!("{} {}", coord.x, coord.y))
+				.collect::<Vec<_>>()
+				.join("\n")
+		})
+		.collect::<Vec<_>>()
+		.join("\n\n")
+}
+
+// Tokens separated by whitespaces and commas, with 1-based column
+fn tokens(line: &str) -> impl Iterator<Item = (usize, &str)> {
+	let mut result = Vec::new();
+	let mut start = None;
+	for (column, (index, c)) in line.char_indices().enumerate() {
+		let is_separator = c.is_whitespace() || c == ',';
+		match (start, is_separator) {
+			(None, false) => start = Some((column + 1, index)),
+			(Some((column, begin)), true) => {
+				result.push((column, &line[begin..index]));
+				start = None;
+			},
+			_ => {},
+		}
+	}
+	if let Some((column, begin)) = start {
+		result.push((column, &line[begin..]));
+	}
+	result.into_iter()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{parse_multipolygon, parse_rings, ParseError, ParseErrorKind};
+
+	fn error(text: &str) -> (usize, usize, ParseErrorKind) {
+		let ParseError { line, column, kind } = parse_rings(text).unwrap_err();
+		(line, column, kind)
+	}
+
+	fn ring_lengths(text: &str) -> Vec<usize> {
+		parse_rings(text)
+			.unwrap()
+			.iter()
+			.map(|ring| ring.0.len())
+			.collect()
+	}
+
+	#[test]
+	fn comments() {
+		let text = "# square\n0 0 # first\n1 0 // second\n// whole line\n1 1\n0 1#no space";
+		let rings = parse_rings(text).unwrap();
+		assert_eq!(rings.len(), 1);
+		assert_eq!(rings[0].0.len(), 4);
+		assert_eq!(rings[0].0[3].x, 0.);
+		assert_eq!(rings[0].0[3].y, 1.);
+	}
+
+	#[test]
+	fn separators() {
+		let rings = parse_rings("0,0\n1, 0\n  1 ,1  \n\t-0.5\t1e0").unwrap();
+		assert_eq!(rings.len(), 1);
+		let coords = rings[0].0.iter().map(|c| (c.x, c.y)).collect::<Vec<_>>();
+		assert_eq!(coords, vec![(0., 0.), (1., 0.), (1., 1.), (-0.5, 1.)]);
+	}
+
+	#[test]
+	fn blank_lines_break_rings() {
+		assert_eq!(
+			ring_lengths("0 0\n1 0\n1 1\n\n\n5 5\n6 5\n6 6\n7 7\n"),
+			vec![3, 4]
+		);
+		// Line with only spaces is blank too, but line with only a comment isn't
+		assert_eq!(ring_lengths("0 0\n1 0\n1 1\n   \n5 5\n6 5\n6 6"), vec![
+			3, 3
+		]);
+		assert_eq!(ring_lengths("0 0\n1 0\n# comment\n1 1"), vec![3]);
+	}
+
+	#[test]
+	fn line_and_column() {
+		assert_eq!(
+			error("0 0\n1 0\n  1 abc"),
+			(3, 5, ParseErrorKind::InvalidNumber("abc".to_owned()))
+		);
+		assert_eq!(
+			error("0 0\n1,,x1\n1 1"),
+			(2, 4, ParseErrorKind::InvalidNumber("x1".to_owned()))
+		);
+		let text = "0 0\n1 0\n1 1\n\n2 2\n3 3 # one more";
+		assert_eq!(error(text), (5, 1, ParseErrorKind::TooFewPoints));
+		assert_eq!(
+			parse_rings(text).unwrap_err().to_string(),
+			"5:1: ring should have at least 3 points"
+		);
+	}
+
+	#[test]
+	fn every_error_kind() {
+		use ParseErrorKind::*;
+
+		assert_eq!(
+			error("0 0\n1 zero\n1 1"),
+			(2, 3, InvalidNumber("zero".to_owned()))
+		);
+		assert_eq!(
+			error("0 0\n1   # y is missing\n1 1"),
+			(2, 2, MissingCoordinate)
+		);
+		assert_eq!(error("0 0\n1 0 2\n1 1"), (2, 5, ExtraToken("2".to_owned())));
+		assert_eq!(error("0 0\n1 0\n\n1 1\n2 1\n2 2"), (1, 1, TooFewPoints));
+		assert_eq!(error(""), (1, 1, NoPoints));
+		assert_eq!(error("\n# only comments\n\n"), (1, 1, NoPoints));
+	}
+
+	#[test]
+	fn non_finite_numbers() {
+		for token in &["NaN", "nan", "inf", "-inf", "infinity", "1e400"] {
+			let text = format!("0 0\n1 {}\n1 1", token);
+			assert_eq!(
+				error(&text),
+				(2, 3, ParseErrorKind::InvalidNumber((*token).to_owned())),
+				"{}",
+				token
+			);
+		}
+	}
+
+	#[test]
+	fn nesting() {
+		// Island inside of the hole comes first, and it's a separate polygon
+		let text = "4 4\n6 4\n6 6\n4 6\n\n0 0\n10 0\n10 10\n0 10\n\n2 2\n2 8\n8 8\n8 2";
+		let polygon = parse_multipolygon(text).unwrap();
+		assert_eq!(polygon.0.len(), 2);
+		assert_eq!(polygon.0[0].exterior().0[0].x, 0.);
+		assert_eq!(polygon.0[0].interiors().len(), 1);
+		assert_eq!(polygon.0[0].interiors()[0].0[0].x, 2.);
+		assert_eq!(polygon.0[1].exterior().0[0].x, 4.);
+		assert!(polygon.0[1].interiors().is_empty());
+	}
+}
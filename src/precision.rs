@@ -0,0 +1,358 @@
+use std::fmt;
+
+use geo::{Point, Rect};
+
+use crate::{
+	verify::Random, CrossingEdge, LineSplitCheck, LineSplitCheckGeneralized,
+	PolygonFastPrecalculator, PolygonFastPrecalculatorPart,
+};
+
+/// Float precision in which generated code is evaluated. GPU uses `F32` for `highp` and may use `F16` for `mediump`/`lowp`.
+#[derive(Clone, Debug, Copy, PartialEq, Eq)]
+pub enum Precision {
+	F64,
+	F32,
+	F16,
+}
+
+impl Default for Precision {
+	fn default() -> Self {
+		Precision::F64
+	}
+}
+
+impl Precision {
+	pub fn round(&self, x: f64) -> f64 {
+		use Precision::*;
+		match self {
+			F64 => x,
+			F32 => x as f32 as f64,
+			F16 => round_to_half(x),
+		}
+	}
+}
+
+fn round_to_half(x: f64) -> f64 {
+	if !x.is_finite() || x == 0. {
+		return x;
+	}
+	if x.abs() >= 65520. {
+		return std::f64::INFINITY.copysign(x);
+	}
+
+	// Half has 10 bits of mantissa, and exponent not less than -14, below that numbers are subnormal
+	let exponent = x.abs().log2().floor().max(-14.);
+	let ulp = 2f64.powf(exponent - 10.);
+	// Ties go to even as in IEEE 754, `round` moves them away from zero
+	let scaled = x / ulp;
+	let mut rounded = scaled.round();
+	if (rounded - scaled).abs() == 0.5 {
+		rounded = 2. * (scaled / 2.).round();
+	}
+	rounded * ulp
+}
+
+#[derive(Clone, Debug)]
+pub struct PrecisionReport {
+	pub precision: Precision,
+	pub samples: usize,
+	pub mismatches: usize,
+	/// Points where result in `precision` differs from result in `f64`, in coordinates of the original polygon.
+	pub mismatched_points: Vec<Point<f64>>,
+}
+
+impl fmt::Display for PrecisionReport {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		writeln!(
+			f,
+			"{:?} mismatches: {} of {} samples",
+			self.precision, self.mismatches, self.samples
+		)?;
+		for point in &self.mismatched_points {
+			writeln!(f, "  {} {}", point.x(), point.y())?;
+		}
+		Ok(())
+	}
+}
+
+impl LineSplitCheck {
+	/// Same as `is_less`, but every operation of generated code is rounded to `precision`.
+	pub fn is_less_with_precision(&self, point: Point<f64>, precision: Precision) -> bool {
+		let r = |x| precision.round(x);
+		let (x, y) = (r(point.x()), r(point.y()));
+		match self {
+			LineSplitCheck::MulToX { k, b } => y < r(r(x * r(*k)) + r(*b)),
+			LineSplitCheck::MulToY { k, b } => x < r(r(y * r(*k)) + r(*b)),
+		}
+	}
+
+	pub fn round_constants(&mut self, precision: Precision) {
+		match self {
+			LineSplitCheck::MulToX { k, b } | LineSplitCheck::MulToY { k, b } => {
+				*k = precision.round(*k);
+				*b = precision.round(*b);
+			},
+		}
+	}
+}
+
+impl CrossingEdge {
+	pub fn is_crossed_with_precision(&self, point: Point<f64>, precision: Precision) -> bool {
+		let r = |x| precision.round(x);
+		let y = r(point.y());
+		r(self.y_min) <= y && y < r(self.y_max) && self.check.check_with_precision(point, precision)
+	}
+
+	pub fn round_constants(&mut self, precision: Precision) {
+		self.y_min = precision.round(self.y_min);
+		self.y_max = precision.round(self.y_max);
+		match &mut self.check {
+			LineSplitCheckGeneralized::Less(check) | LineSplitCheckGeneralized::Greater(check) => {
+				check.round_constants(precision)
+			},
+		}
+	}
+}
+
+impl LineSplitCheckGeneralized {
+	pub fn check_with_precision(&self, point: Point<f64>, precision: Precision) -> bool {
+		use LineSplitCheckGeneralized::*;
+		match self {
+			Less(check) => check.is_less_with_precision(point, precision),
+			Greater(check) => !check.is_less_with_precision(point, precision),
+		}
+	}
+}
+
+impl PolygonFastPrecalculatorPart {
+	pub fn round_constants(&mut self, precision: Precision) {
+		use PolygonFastPrecalculatorPart::*;
+		match self {
+			LineSplit {
+				check,
+				less,
+				greater,
+			} => {
+				check.round_constants(precision);
+				less.round_constants(precision);
+				greater.round_constants(precision);
+			},
+			Triangle { checks } => {
+				for check in checks.iter_mut() {
+					match check {
+						LineSplitCheckGeneralized::Less(check)
+						| LineSplitCheckGeneralized::Greater(check) => check.round_constants(precision),
+					}
+				}
+			},
+			Edges { edges } => {
+				for edge in edges.iter_mut() {
+					edge.round_constants(precision);
+				}
+			},
+			None => {},
+		}
+	}
+}
+
+impl PolygonFastPrecalculator {
+	/// Emulates evaluation of generated code in `precision`, including normalization into bounding rect.
+	pub fn is_inside_with_precision(&self, point: Point<f64>, precision: Precision) -> bool {
+		fn is_inside_inner(
+			check: &PolygonFastPrecalculatorPart,
+			point: Point<f64>,
+			precision: Precision,
+		) -> bool {
+			use PolygonFastPrecalculatorPart::*;
+			match check {
+				LineSplit {
+					check,
+					less,
+					greater,
+				} => {
+					if check.is_less_with_precision(point, precision) {
+						is_inside_inner(&**less, point, precision)
+					} else {
+						is_inside_inner(&**greater, point, precision)
+					}
+				},
+				Triangle { checks } => checks
+					.iter()
+					.all(|c| c.check_with_precision(point, precision)),
+				Edges { edges } => {
+					edges
+						.iter()
+						.filter(|e| e.is_crossed_with_precision(point, precision))
+						.count() % 2 == 1
+				},
+				None => false,
+			}
+		}
+
+		let r = |x| precision.round(x);
+		let min = self.bounding_rect.min();
+		let point = Point::new(
+			r(r(r(point.x()) - r(min.x)) / r(self.bounding_rect.width())),
+			r(r(r(point.y()) - r(min.y)) / r(self.bounding_rect.height())),
+		);
+
+		if !(0. <= point.x() && point.x() <= 1.) {
+			return false;
+		}
+		if !(0. <= point.y() && point.y() <= 1.) {
+			return false;
+		}
+
+		is_inside_inner(&self.parts, point, precision)
+	}
+
+	/// Rounds all constants of the tree, so generated code contains numbers that are exact in `precision` and `is_inside` works with the same numbers as GPU.
+	pub fn round_constants(&mut self, precision: Precision) {
+		let min = self.bounding_rect.min();
+		let min = (precision.round(min.x), precision.round(min.y));
+		let size = (
+			precision.round(self.bounding_rect.width()),
+			precision.round(self.bounding_rect.height()),
+		);
+		self.bounding_rect = Rect::new(min, (min.0 + size.0, min.1 + size.1));
+		self.parts.round_constants(precision);
+	}
+
+	/// Finds points where generated code evaluated in `precision` gives another result than in `f64`. Points are sampled randomly and near every line of the tree, because only there results can differ.
+	pub fn precision_report(&self, precision: Precision, samples: usize) -> PrecisionReport {
+		let mut lines = Vec::new();
+		self.parts.visit(&mut |part, _| {
+			use PolygonFastPrecalculatorPart::*;
+			match part {
+				LineSplit { check, .. } => lines.push(*check),
+				Triangle { checks } => lines.extend(checks.iter().map(|check| match check {
+					LineSplitCheckGeneralized::Less(check)
+					| LineSplitCheckGeneralized::Greater(check) => *check,
+				})),
+				Edges { edges } => lines.extend(edges.iter().map(|edge| match edge.check {
+					LineSplitCheckGeneralized::Less(check)
+					| LineSplitCheckGeneralized::Greater(check) => check,
+				})),
+				None => {},
+			}
+		});
+
+		let min = self.bounding_rect.min();
+		let (width, height) = (self.bounding_rect.width(), self.bounding_rect.height());
+		let to_original = |x: f64, y: f64| Point::new(min.x + x * width, min.y + y * height);
+
+		let mut random = Random::new(samples as u64);
+		let mut points = Vec::with_capacity(samples);
+		while points.len() < samples {
+			let (x, y) = (random.next_f64(), random.next_f64());
+			if lines.is_empty() || points.len() % 4 == 0 {
+				points.push(to_original(x, y));
+			} else {
+				// Point on the line plus jitter of few ulps of `f16`
+				let jitter = (random.next_f64() * 2. - 1.) * 1e-3;
+				match lines[random.next_usize(lines.len())] {
+					LineSplitCheck::MulToX { k, b } => {
+						points.push(to_original(x, x * k + b + jitter))
+					},
+					LineSplitCheck::MulToY { k, b } => {
+						points.push(to_original(y * k + b + jitter, y))
+					},
+				}
+			}
+		}
+
+		let mut report = PrecisionReport {
+			precision,
+			samples: points.len(),
+			mismatches: 0,
+			mismatched_points: Vec::new(),
+		};
+		for point in points {
+			if self.is_inside(point) != self.is_inside_with_precision(point, precision) {
+				report.mismatches += 1;
+				report.mismatched_points.push(point);
+			}
+		}
+		report
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use geo::Point;
+
+	use super::{round_to_half, Precision};
+	use crate::{vec_to_multipolygon, PolygonFastPrecalculator, PolygonFastPrecalculatorPart};
+
+	#[test]
+	fn half() {
+		assert_eq!(round_to_half(1.), 1.);
+		assert_eq!(round_to_half(-0.1), -0.0999755859375);
+		assert_eq!(round_to_half(65504.), 65504.);
+
+		// Ties to even
+		let ulp = 2f64.powi(-10);
+		assert_eq!(round_to_half(1. + ulp / 2.), 1.);
+		assert_eq!(round_to_half(1. + 1.5 * ulp), 1. + 2. * ulp);
+		assert_eq!(round_to_half(-1. - ulp / 2.), -1.);
+		assert_eq!(round_to_half(2048. + 1.), 2048.);
+		assert_eq!(round_to_half(2048. + 3.), 2052.);
+
+		// Subnormals are multiples of 2^-24
+		let min = 2f64.powi(-24);
+		assert_eq!(round_to_half(min), min);
+		assert_eq!(round_to_half(3.4 * min), 3. * min);
+		assert_eq!(round_to_half(0.5 * min), 0.);
+		assert_eq!(round_to_half(0.6 * min), min);
+		assert_eq!(round_to_half(2f64.powi(-15) + 0.5 * min), 2f64.powi(-15));
+
+		// Half of the ulp above the max half rounds to infinity
+		assert_eq!(round_to_half(65519.), 65504.);
+		assert_eq!(round_to_half(65520.), std::f64::INFINITY);
+		assert_eq!(round_to_half(-1e10), std::f64::NEG_INFINITY);
+		assert!(round_to_half(std::f64::NAN).is_nan());
+	}
+
+	#[test]
+	fn round_constants() {
+		let polygon = vec_to_multipolygon(vec![
+			(0.1, 0.2),
+			(4.3, 0.),
+			(4.1, 3.7),
+			(2.2, 1.3),
+			(0., 3.),
+		]);
+		let tree = PolygonFastPrecalculator::calc("test".to_owned(), polygon);
+		for &precision in &[Precision::F32, Precision::F16] {
+			let mut rounded = tree.clone();
+			rounded.round_constants(precision);
+			assert_eq!(
+				rounded.to_string().lines().count(),
+				tree.to_string().lines().count()
+			);
+
+			let exact = |x: f64| precision.round(x) == x;
+			let min = rounded.bounding_rect.min();
+			assert!(exact(min.x) && exact(min.y));
+			assert!(exact(rounded.bounding_rect.width()));
+			assert!(exact(rounded.bounding_rect.height()));
+			let mut count = 0;
+			rounded.parts.visit(&mut |part, _| {
+				if let PolygonFastPrecalculatorPart::LineSplit { check, .. } = part {
+					let mut again = *check;
+					again.round_constants(precision);
+					assert_eq!(again, *check);
+					count += 1;
+				}
+			});
+			assert!(count > 0);
+
+			// Far from the boundary the rounded tree gives the same results
+			for &(x, y, inside) in &[(1., 1., true), (3.5, 1., true), (2.2, 3., false)] {
+				let point = Point::new(x, y);
+				assert_eq!(rounded.is_inside(point), inside);
+				assert_eq!(rounded.is_inside_with_precision(point, precision), inside);
+			}
+		}
+	}
+}
@@ -0,0 +1,193 @@
+use geo::{Coordinate, MultiPolygon, Point};
+
+use crate::{image::Image, PolygonFastPrecalculator};
+
+const INSIDE: (u8, u8, u8) = (0, 0, 0);
+const OUTSIDE: (u8, u8, u8) = (255, 255, 255);
+
+/// Orthographic view of the plane of the demo. Image height covers `1 / zoom` of the plane around `center`, y axis points up.
+#[derive(Clone, Debug, Copy)]
+pub struct View {
+	pub center: Coordinate<f64>,
+	pub zoom: f64,
+}
+
+impl Default for View {
+	/// Whole polygon, the plane square `[-0.5, 0.5]²` fits the image height.
+	fn default() -> Self {
+		Self {
+			center: Coordinate { x: 0., y: 0. },
+			zoom: 1.,
+		}
+	}
+}
+
+impl View {
+	/// Point of the plane under the center of the pixel.
+	pub fn to_plane(&self, x: usize, y: usize, width: usize, height: usize) -> Coordinate<f64> {
+		let h = height as f64;
+		Coordinate {
+			x: self.center.x + (x as f64 + 0.5 - width as f64 / 2.) / h / self.zoom,
+			y: self.center.y + (h / 2. - y as f64 - 0.5) / h / self.zoom,
+		}
+	}
+}
+
+impl PolygonFastPrecalculator {
+	/// Point of the polygon at the point of the plane, same as `coord` in `frag_after.glsl` with `offset` and `size` of the bounding rect.
+	pub fn plane_to_polygon(&self, pos: Coordinate<f64>) -> Coordinate<f64> {
+		let offset = self.bounding_rect.min();
+		let (width, height) = (self.bounding_rect.width(), self.bounding_rect.height());
+		let prop = width.max(height);
+		Coordinate {
+			x: (pos.x + 0.5 * width / prop) * prop + offset.x,
+			y: (pos.y + 0.5 * height / prop) * prop + offset.y,
+		}
+	}
+
+	/// Renders `is_inside` on CPU, inside is black and outside is white, so it can be compared with the shader output or with `rasterize_reference`.
+	pub fn rasterize(&self, view: &View, width: usize, height: usize) -> Image {
+		self.rasterize_with(view, width, height, |point| self.is_inside(point))
+	}
+
+	/// Renders `original` polygon with the fill rule of the tree in the same way as `rasterize`, pixels where images differ are mismatches of the tree.
+	pub fn rasterize_reference(
+		&self,
+		original: &MultiPolygon<f64>,
+		view: &View,
+		width: usize,
+		height: usize,
+	) -> Image {
+		self.rasterize_with(view, width, height, |point| {
+			self.fill_rule.contains(original, point)
+		})
+	}
+
+	fn rasterize_with(
+		&self,
+		view: &View,
+		width: usize,
+		height: usize,
+		is_inside: impl Fn(Point<f64>) -> bool,
+	) -> Image {
+		let mut image = Image::new(width, height);
+		for y in 0..height {
+			for x in 0..width {
+				let coord = self.plane_to_polygon(view.to_plane(x, y, width, height));
+				let color = if is_inside(Point(coord)) {
+					INSIDE
+				} else {
+					OUTSIDE
+				};
+				image.set_pixel(x, y, color);
+			}
+		}
+		image
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use geo::{Coordinate, LineString, MultiPolygon, Polygon};
+
+	use super::{View, INSIDE};
+	use crate::{fill_rule::FillRule, CalcOptions, PolygonFastPrecalculator};
+
+	const SIZE: usize = 64;
+
+	// Only pixels whose centers lie almost exactly on an edge can differ
+	const MAX_DIFFERENCES: usize = 8;
+
+	fn ring(points: &[(f64, f64)]) -> LineString<f64> {
+		LineString::from(points.to_vec())
+	}
+
+	fn fixtures() -> Vec<(&'static str, MultiPolygon<f64>)> {
+		let comb = ring(&[
+			(0., 0.),
+			(5., 0.),
+			(5., 3.),
+			(4., 3.),
+			(4., 1.),
+			(3., 1.),
+			(3., 3.),
+			(2., 3.),
+			(2., 1.),
+			(1., 1.),
+			(1., 3.),
+			(0., 3.),
+		]);
+		let holed = Polygon::new(ring(&[(0., 0.), (4., 0.), (4., 4.), (0., 4.)]), vec![ring(
+			&[(1., 1.), (1., 3.), (3., 3.), (3., 1.)],
+		)]);
+		let pentagram = ring(
+			&(0..5)
+				.map(|i| {
+					let angle =
+						std::f64::consts::FRAC_PI_2 + i as f64 * 4. * std::f64::consts::PI / 5.;
+					(angle.cos(), angle.sin())
+				})
+				.collect::<Vec<_>>(),
+		);
+		vec![
+			("comb", MultiPolygon(vec![Polygon::new(comb, vec![])])),
+			("holed", MultiPolygon(vec![holed])),
+			(
+				"pentagram",
+				MultiPolygon(vec![Polygon::new(pentagram, vec![])]),
+			),
+		]
+	}
+
+	#[test]
+	fn tree_matches_reference() {
+		let views = [View::default(), View {
+			center: Coordinate { x: 0.1, y: -0.1 },
+			zoom: 2.5,
+		}];
+		for (name, polygon) in fixtures() {
+			for &fill_rule in &FillRule::ALL {
+				for &max_edges_per_leaf in &[0, 8] {
+					let options = CalcOptions {
+						fill_rule,
+						max_edges_per_leaf,
+						..CalcOptions::default()
+					};
+					let tree = PolygonFastPrecalculator::calc_with_options(
+						name.to_owned(),
+						polygon.clone(),
+						&options,
+					);
+					for view in &views {
+						let image = tree.rasterize(view, SIZE, SIZE);
+						let reference = tree.rasterize_reference(&polygon, view, SIZE, SIZE);
+						let differences = image.count_differences(&reference);
+						assert!(
+							differences <= MAX_DIFFERENCES,
+							"{}, {}, max edges {}, {:?}: {} pixels differ",
+							name,
+							fill_rule.name(),
+							max_edges_per_leaf,
+							view,
+							differences
+						);
+					}
+				}
+			}
+		}
+	}
+
+	#[test]
+	fn hole_is_outside() {
+		let (_, polygon) = fixtures().remove(1);
+		let tree = PolygonFastPrecalculator::calc("holed".to_owned(), polygon.clone());
+		let image = tree.rasterize(&View::default(), SIZE, SIZE);
+		assert_ne!(image.get_pixel(SIZE / 2, SIZE / 2), INSIDE);
+		assert_eq!(image.get_pixel(SIZE / 8, SIZE / 2), INSIDE);
+
+		// Reference of another polygon is caught
+		let (_, comb) = fixtures().remove(0);
+		let other = tree.rasterize_reference(&comb, &View::default(), SIZE, SIZE);
+		assert!(image.count_differences(&other) > SIZE * SIZE / 10);
+	}
+}
@@ -0,0 +1,371 @@
+use std::fmt::Write;
+
+use geo::{prelude::BoundingRect, Coordinate, Line, MultiPolygon, Point, Rect};
+
+use crate::{
+	fill_rule::oriented_rings, fit_point_into_default_borders, Constant, FillRule, Normalization,
+};
+
+/// Straightforward point in polygon test that checks every edge of the polygon.
+#[derive(Clone, Debug, Copy, PartialEq, Eq)]
+pub enum ReferenceMethod {
+	/// Parity of edges crossed by a horizontal ray, the same as `FillRule::EvenOdd`.
+	CrossingNumber,
+	/// Winding number of the polygon, checked with the fill rule.
+	WindingNumber(FillRule),
+}
+
+#[derive(Clone, Debug, Copy, PartialEq, Eq)]
+pub enum Language {
+	Glsl,
+	/// Function takes `[f32; 2]` instead of `vec2`.
+	Rust,
+}
+
+/// Code of `is_inside_<name>` function with the same signature and bounding rect check as the code of the tree, but with edges of the polygon normalized to `[0, 1]²` and baked as a constant array. It's a baseline to compare the speed of the tree with, and a fallback for shapes where the tree is too big. GLSL ES 1.00 has no array initializers, so the array is filled at the start of the function.
+pub fn reference_code(
+	name: &str,
+	polygon: &MultiPolygon<f64>,
+	method: ReferenceMethod,
+	language: Language,
+) -> String {
+	let rect = reference_rect(polygon);
+	let edges = reference_edges(polygon);
+	match language {
+		Language::Glsl => glsl_code(name, &rect, &edges, method, false),
+		Language::Rust => rust_code(name, &rect, &edges, method),
+	}
+}
+
+/// GLSL code of `reference_code` that reads edges from `uniform vec4 edges_<name>[N]` instead of the constant array, so they are not filled on every call. Edges are uploaded as `vec4(start, end)` from `reference_edges`.
+pub fn reference_uniform_code(
+	name: &str,
+	polygon: &MultiPolygon<f64>,
+	method: ReferenceMethod,
+) -> String {
+	let rect = reference_rect(polygon);
+	let edges = reference_edges(polygon);
+	glsl_code(name, &rect, &edges, method, true)
+}
+
+/// Edges of the polygon that `reference_code` checks, normalized to `[0, 1]²`.
+pub fn reference_edges(polygon: &MultiPolygon<f64>) -> Vec<Line<f64>> {
+	let rect = reference_rect(polygon);
+	// Holes are oriented opposite to their exteriors, so the winding number is the same as in `FillRule::contains`
+	oriented_rings(polygon)
+		.iter()
+		.map(|ring| ring.lines())
+		.flatten()
+		.filter(|line| line.dx() != 0. || line.dy() != 0.)
+		.map(|line| {
+			Line::new(
+				fit_point_into_default_borders(Point(line.start), &rect),
+				fit_point_into_default_borders(Point(line.end), &rect),
+			)
+		})
+		.collect()
+}
+
+fn reference_rect(polygon: &MultiPolygon<f64>) -> Rect<f64> {
+	polygon
+		.bounding_rect()
+		.unwrap_or_else(|| Rect::new(Coordinate { x: 0., y: 0. }, Coordinate { x: 1., y: 1. }))
+}
+
+fn glsl_code(
+	name: &str,
+	rect: &Rect<f64>,
+	edges: &[Line<f64>],
+	method: ReferenceMethod,
+	uniform: bool,
+) -> String {
+	let mut result = String::new();
+	// Empty arrays are not allowed
+	if uniform && !edges.is_empty() {
+		writeln!(result, "uniform vec4 edges_{}[{}];", name, edges.len()).unwrap();
+	}
+	writeln!(result, "bool is_inside_{}(vec2 a) {{", name).unwrap();
+	writeln!(result, "  {}", Normalization(rect)).unwrap();
+	writeln!(
+		result,
+		"  if (0. <= a.x && a.x <= 1. && 0. <= a.y && a.y <= 1.) {{"
+	)
+	.unwrap();
+	if edges.is_empty() {
+		writeln!(result, "    return false;").unwrap();
+	} else {
+		if !uniform {
+			writeln!(result, "    vec4 edges[{}];", edges.len()).unwrap();
+			for (i, edge) in edges.iter().enumerate() {
+				writeln!(
+					result,
+					"    edges[{}] = vec4({}, {}, {}, {});",
+					i,
+					Constant(edge.start.x),
+					Constant(edge.start.y),
+					Constant(edge.end.x),
+					Constant(edge.end.y)
+				)
+				.unwrap();
+			}
+		}
+		match method {
+			ReferenceMethod::CrossingNumber => writeln!(result, "    bool inside = false;"),
+			ReferenceMethod::WindingNumber(_) => writeln!(result, "    int winding = 0;"),
+		}
+		.unwrap();
+		writeln!(result, "    for (int i = 0; i < {}; i++) {{", edges.len()).unwrap();
+		if uniform {
+			writeln!(result, "      vec4 e = edges_{}[i];", name).unwrap();
+		} else {
+			writeln!(result, "      vec4 e = edges[i];").unwrap();
+		}
+		match method {
+			ReferenceMethod::CrossingNumber => {
+				writeln!(result, "      if ((e.y > a.y) != (e.w > a.y) && a.x < (e.z - e.x) * (a.y - e.y) / (e.w - e.y) + e.x) {{").unwrap();
+				writeln!(result, "        inside = !inside;").unwrap();
+				writeln!(result, "      }}").unwrap();
+			},
+			ReferenceMethod::WindingNumber(_) => {
+				writeln!(
+					result,
+					"      float side = (e.z - e.x) * (a.y - e.y) - (a.x - e.x) * (e.w - e.y);"
+				)
+				.unwrap();
+				writeln!(result, "      if (e.y <= a.y) {{").unwrap();
+				writeln!(result, "        if (e.w > a.y && side > 0.) {{").unwrap();
+				writeln!(result, "          winding += 1;").unwrap();
+				writeln!(result, "        }}").unwrap();
+				writeln!(result, "      }} else if (e.w <= a.y && side < 0.) {{").unwrap();
+				writeln!(result, "        winding -= 1;").unwrap();
+				writeln!(result, "      }}").unwrap();
+			},
+		}
+		writeln!(result, "    }}").unwrap();
+		match method {
+			ReferenceMethod::CrossingNumber => writeln!(result, "    return inside;"),
+			// There is no `%` for integers in GLSL ES 1.00
+			ReferenceMethod::WindingNumber(FillRule::EvenOdd) => {
+				writeln!(result, "    return winding - winding / 2 * 2 != 0;")
+			},
+			ReferenceMethod::WindingNumber(rule) => {
+				writeln!(result, "    return {};", winding_check(rule))
+			},
+		}
+		.unwrap();
+	}
+	writeln!(result, "  }} else {{").unwrap();
+	writeln!(result, "    return false;").unwrap();
+	writeln!(result, "  }}").unwrap();
+	writeln!(result, "}}").unwrap();
+	result
+}
+
+fn rust_code(name: &str, rect: &Rect<f64>, edges: &[Line<f64>], method: ReferenceMethod) -> String {
+	let mut result = String::new();
+	writeln!(result, "pub fn is_inside_{}(a: [f32; 2]) -> bool {{", name).unwrap();
+	writeln!(result, "  const EDGES: [[f32; 4]; {}] = [", edges.len()).unwrap();
+	for edge in edges {
+		writeln!(
+			result,
+			"    [{}, {}, {}, {}],",
+			Constant(edge.start.x),
+			Constant(edge.start.y),
+			Constant(edge.end.x),
+			Constant(edge.end.y)
+		)
+		.unwrap();
+	}
+	writeln!(result, "  ];").unwrap();
+	writeln!(
+		result,
+		"  let a = [(a[0] - {}) / {}, (a[1] - {}) / {}];",
+		Constant(rect.min().x),
+		Constant(rect.width()),
+		Constant(rect.min().y),
+		Constant(rect.height())
+	)
+	.unwrap();
+	writeln!(
+		result,
+		"  if !(0. <= a[0] && a[0] <= 1. && 0. <= a[1] && a[1] <= 1.) {{"
+	)
+	.unwrap();
+	writeln!(result, "    return false;").unwrap();
+	writeln!(result, "  }}").unwrap();
+	match method {
+		ReferenceMethod::CrossingNumber => {
+			writeln!(result, "  let mut inside = false;").unwrap();
+			writeln!(result, "  for e in EDGES.iter() {{").unwrap();
+			writeln!(result, "    if (e[1] > a[1]) != (e[3] > a[1]) && a[0] < (e[2] - e[0]) * (a[1] - e[1]) / (e[3] - e[1]) + e[0] {{").unwrap();
+			writeln!(result, "      inside = !inside;").unwrap();
+			writeln!(result, "    }}").unwrap();
+			writeln!(result, "  }}").unwrap();
+			writeln!(result, "  inside").unwrap();
+		},
+		ReferenceMethod::WindingNumber(rule) => {
+			writeln!(result, "  let mut winding = 0;").unwrap();
+			writeln!(result, "  for e in EDGES.iter() {{").unwrap();
+			writeln!(
+				result,
+				"    let side = (e[2] - e[0]) * (a[1] - e[1]) - (a[0] - e[0]) * (e[3] - e[1]);"
+			)
+			.unwrap();
+			writeln!(result, "    if e[1] <= a[1] {{").unwrap();
+			writeln!(result, "      if e[3] > a[1] && side > 0. {{").unwrap();
+			writeln!(result, "        winding += 1;").unwrap();
+			writeln!(result, "      }}").unwrap();
+			writeln!(result, "    }} else if e[3] <= a[1] && side < 0. {{").unwrap();
+			writeln!(result, "      winding -= 1;").unwrap();
+			writeln!(result, "    }}").unwrap();
+			writeln!(result, "  }}").unwrap();
+			writeln!(result, "  {}", winding_check(rule)).unwrap();
+		},
+	}
+	writeln!(result, "}}").unwrap();
+	result
+}
+
+// Same as `FillRule::is_filled`
+fn winding_check(rule: FillRule) -> &'static str {
+	match rule {
+		FillRule::EvenOdd => "winding % 2 != 0",
+		FillRule::NonZero => "winding != 0",
+		FillRule::Positive => "winding > 0",
+		FillRule::Negative => "winding < 0",
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use geo::{Coordinate, Line, LineString, MultiPolygon, Point, Polygon};
+
+	use super::{reference_edges, reference_rect};
+	use crate::FillRule;
+
+	fn square_with_hole(exterior_ccw: bool, hole_ccw: bool) -> MultiPolygon<f64> {
+		let ring = |coords: &[(f64, f64)], ccw: bool| {
+			let mut coords = coords.to_vec();
+			if !ccw {
+				coords.reverse();
+			}
+			LineString::from(coords)
+		};
+		MultiPolygon(vec![Polygon::new(
+			ring(&[(0., 0.), (4., 0.), (4., 4.), (0., 4.)], exterior_ccw),
+			vec![ring(&[(1., 1.), (3., 1.), (3., 3.), (1., 3.)], hole_ccw)],
+		)])
+	}
+
+	fn pentagram() -> MultiPolygon<f64> {
+		MultiPolygon(vec![Polygon::new(
+			LineString::from(vec![
+				(0., 1.),
+				(-0.5878, -0.809),
+				(0.9511, 0.309),
+				(-0.9511, 0.309),
+				(0.5878, -0.809),
+			]),
+			vec![],
+		)])
+	}
+
+	fn signed_area(edges: &[Line<f64>]) -> f64 {
+		edges
+			.iter()
+			.map(|edge| edge.start.x * edge.end.y - edge.end.x * edge.start.y)
+			.sum::<f64>()
+			/ 2.
+	}
+
+	// Loops of the generated code
+	fn winding(edges: &[Line<f64>], a: Coordinate<f64>) -> i32 {
+		let mut winding = 0;
+		for e in edges {
+			let side = (e.end.x - e.start.x) * (a.y - e.start.y)
+				- (a.x - e.start.x) * (e.end.y - e.start.y);
+			if e.start.y <= a.y {
+				if e.end.y > a.y && side > 0. {
+					winding += 1;
+				}
+			} else if e.end.y <= a.y && side < 0. {
+				winding -= 1;
+			}
+		}
+		winding
+	}
+
+	fn crossing(edges: &[Line<f64>], a: Coordinate<f64>) -> bool {
+		let mut inside = false;
+		for e in edges {
+			if (e.start.y > a.y) != (e.end.y > a.y)
+				&& a.x
+					< (e.end.x - e.start.x) * (a.y - e.start.y) / (e.end.y - e.start.y) + e.start.x
+			{
+				inside = !inside;
+			}
+		}
+		inside
+	}
+
+	#[test]
+	fn holes_are_opposite_to_exteriors() {
+		for &exterior_ccw in &[true, false] {
+			for &hole_ccw in &[true, false] {
+				let edges = reference_edges(&square_with_hole(exterior_ccw, hole_ccw));
+				assert_eq!(edges.len(), 8);
+				let exterior = signed_area(&edges[..4]);
+				let hole = signed_area(&edges[4..]);
+				assert_eq!(exterior > 0., exterior_ccw);
+				assert!(exterior * hole < 0., "{} {}", exterior, hole);
+
+				let inside = if exterior_ccw { 1 } else { -1 };
+				assert_eq!(winding(&edges, Coordinate { x: 0.1, y: 0.5 }), inside);
+				assert_eq!(winding(&edges, Coordinate { x: 0.5, y: 0.5 }), 0);
+			}
+		}
+	}
+
+	#[test]
+	fn loops_match_fill_rules() {
+		let polygons = [
+			pentagram(),
+			square_with_hole(true, true),
+			square_with_hole(false, true),
+		];
+		for polygon in &polygons {
+			let rect = reference_rect(polygon);
+			let edges = reference_edges(polygon);
+			// Low discrepancy sequence in the bounding rect and around it, it never hits edges exactly
+			let points = (0..2000).map(|i| {
+				let x = (i as f64 * 0.618_033_988_75).fract() * 1.2 - 0.1;
+				let y = (i as f64 * 0.414_213_562_37).fract() * 1.2 - 0.1;
+				(
+					Coordinate { x, y },
+					Point::new(
+						rect.min().x + x * rect.width(),
+						rect.min().y + y * rect.height(),
+					),
+				)
+			});
+			for (normalized, point) in points {
+				let winding = winding(&edges, normalized);
+				for &rule in &FillRule::ALL {
+					assert_eq!(
+						rule.is_filled(winding),
+						rule.contains(polygon, point),
+						"{:?} {:?}",
+						rule,
+						point
+					);
+				}
+				assert_eq!(
+					crossing(&edges, normalized),
+					FillRule::EvenOdd.contains(polygon, point),
+					"{:?}",
+					point
+				);
+			}
+		}
+	}
+}
@@ -0,0 +1,280 @@
+use std::{fmt::Write as _, fs::File, io, io::BufWriter, path::Path};
+
+use geo::{Coordinate, Line, MultiPolygon, Point, Rect};
+
+use crate::{
+	edges, fill_rule::distance_to_segment, Constant, Normalization, PolygonFastPrecalculator,
+};
+
+/// Signed distance field of the polygon baked into a grayscale texture, for shapes that are too complex for any generated code. Texture covers the bounding rect of the polygon extended by `spread` on every side.
+#[derive(Clone, Debug)]
+pub struct DistanceField {
+	pub name: String,
+	pub bounding_rect: Rect<f64>,
+	/// Distance in coordinates of the polygon that maps to the full range of the texture, larger distances are clamped.
+	pub spread: f64,
+	pub width: usize,
+	pub height: usize,
+	/// `0.5 + 0.5 * distance / spread`, distance is positive inside, row by row from the top.
+	pub data: Vec<u8>,
+}
+
+impl PolygonFastPrecalculator {
+	/// Distance field of `original`, which this tree was calculated for. Sign of every pixel is taken from `is_inside` of the tree, so the texture is filled exactly as the generated code, and distance is to the nearest edge of the polygon resolved with the fill rule. Largest side of the texture is `size` pixels, `spread` is a positive fraction of the largest side of the bounding rect.
+	pub fn distance_field(
+		&self,
+		original: &MultiPolygon<f64>,
+		size: usize,
+		spread: f64,
+	) -> DistanceField {
+		assert!(size > 0, "distance field should have at least one pixel");
+		assert!(
+			spread > 0.,
+			"spread of the distance field should be positive"
+		);
+		let rect = self.bounding_rect;
+		let spread = spread * rect.width().max(rect.height());
+		let min = Coordinate {
+			x: rect.min().x - spread,
+			y: rect.min().y - spread,
+		};
+		let (field_width, field_height) = (rect.width() + 2. * spread, rect.height() + 2. * spread);
+		let scale = size as f64 / field_width.max(field_height);
+		let width = ((field_width * scale).round() as usize).max(1);
+		let height = ((field_height * scale).round() as usize).max(1);
+
+		let resolved = self.fill_rule.resolve(original);
+		let buckets = Buckets::new(
+			edges(&resolved).collect(),
+			Rect::new(min, Coordinate {
+				x: min.x + field_width,
+				y: min.y + field_height,
+			}),
+			spread,
+		);
+
+		let mut data = Vec::with_capacity(width * height);
+		for y in 0..height {
+			for x in 0..width {
+				let point = Coordinate {
+					x: min.x + (x as f64 + 0.5) / width as f64 * field_width,
+					y: min.y + field_height - (y as f64 + 0.5) / height as f64 * field_height,
+				};
+				let distance = buckets.distance(point).min(spread);
+				let distance = if self.is_inside(Point(point)) {
+					distance
+				} else {
+					-distance
+				};
+				let value = (0.5 + 0.5 * distance / spread).max(0.).min(1.);
+				data.push((value * 255.).round() as u8);
+			}
+		}
+
+		DistanceField {
+			name: self.name.clone(),
+			bounding_rect: rect,
+			spread,
+			width,
+			height,
+			data,
+		}
+	}
+}
+
+impl DistanceField {
+	pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+		let file = File::create(path)?;
+		let ref mut wr = BufWriter::new(file);
+
+		let mut encoder = png::Encoder::new(wr, self.width as u32, self.height as u32);
+		encoder.set_color(png::ColorType::Grayscale);
+		encoder.set_depth(png::BitDepth::Eight);
+		let mut writer = encoder.write_header()?;
+
+		writer.write_image_data(&self.data)?;
+		Ok(())
+	}
+
+	/// GLSL functions `float distance_<name>(sampler2D field, vec2 a)`, that returns signed distance in coordinates of the polygon, and `bool is_inside_<name>(sampler2D field, vec2 a)`. They normalize the point as the code of the tree does, and expect the texture uploaded as is, with the first row of the image at `v = 0`, and linear filtering.
+	pub fn sampler_code(&self) -> String {
+		let rect = &self.bounding_rect;
+		let padding = (self.spread / rect.width(), self.spread / rect.height());
+
+		let mut result = String::new();
+		writeln!(
+			result,
+			"float distance_{}(sampler2D field, vec2 a) {{",
+			self.name
+		)
+		.unwrap();
+		writeln!(result, "  {}", Normalization(rect)).unwrap();
+		writeln!(
+			result,
+			"  vec2 uv = (a + vec2({}, {})) / vec2({}, {});",
+			Constant(padding.0),
+			Constant(padding.1),
+			Constant(1. + 2. * padding.0),
+			Constant(1. + 2. * padding.1)
+		)
+		.unwrap();
+		writeln!(
+			result,
+			"  if (0. <= uv.x && uv.x <= 1. && 0. <= uv.y && uv.y <= 1.) {{"
+		)
+		.unwrap();
+		writeln!(
+			result,
+			"    return (texture2D(field, vec2(uv.x, 1. - uv.y)).r - 0.5) * {};",
+			Constant(2. * self.spread)
+		)
+		.unwrap();
+		writeln!(result, "  }} else {{").unwrap();
+		writeln!(result, "    return {};", Constant(-self.spread)).unwrap();
+		writeln!(result, "  }}").unwrap();
+		writeln!(result, "}}").unwrap();
+		writeln!(
+			result,
+			"bool is_inside_{}(sampler2D field, vec2 a) {{",
+			self.name
+		)
+		.unwrap();
+		writeln!(result, "  return distance_{}(field, a) > 0.;", self.name).unwrap();
+		writeln!(result, "}}").unwrap();
+		result
+	}
+}
+
+// Edges by square buckets of `spread` size, edges further than `spread` from a point don't change its pixel
+struct Buckets {
+	rect: Rect<f64>,
+	size: f64,
+	columns: usize,
+	rows: usize,
+	edges: Vec<Vec<Line<f64>>>,
+}
+
+impl Buckets {
+	const MAX_SIDE: usize = 256;
+
+	fn new(edges: Vec<Line<f64>>, rect: Rect<f64>, spread: f64) -> Self {
+		let size = spread.max(rect.width().max(rect.height()) / Self::MAX_SIDE as f64);
+		let columns = ((rect.width() / size).ceil() as usize).max(1);
+		let rows = ((rect.height() / size).ceil() as usize).max(1);
+		let mut result = Self {
+			rect,
+			size,
+			columns,
+			rows,
+			edges: vec![Vec::new(); columns * rows],
+		};
+		for edge in edges {
+			let (min_column, min_row) = result.bucket(Coordinate {
+				x: edge.start.x.min(edge.end.x) - spread,
+				y: edge.start.y.min(edge.end.y) - spread,
+			});
+			let (max_column, max_row) = result.bucket(Coordinate {
+				x: edge.start.x.max(edge.end.x) + spread,
+				y: edge.start.y.max(edge.end.y) + spread,
+			});
+			for row in min_row..=max_row {
+				for column in min_column..=max_column {
+					result.edges[row * columns + column].push(edge);
+				}
+			}
+		}
+		result
+	}
+
+	fn bucket(&self, coord: Coordinate<f64>) -> (usize, usize) {
+		let column = ((coord.x - self.rect.min().x) / self.size).max(0.) as usize;
+		let row = ((coord.y - self.rect.min().y) / self.size).max(0.) as usize;
+		(column.min(self.columns - 1), row.min(self.rows - 1))
+	}
+
+	// Infinity when there are no edges near the point
+	fn distance(&self, point: Coordinate<f64>) -> f64 {
+		let (column, row) = self.bucket(point);
+		self.edges[row * self.columns + column]
+			.iter()
+			.map(|edge| distance_to_segment(edge, point))
+			.fold(f64::INFINITY, f64::min)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use geo::{Coordinate, LineString, MultiPolygon, Point, Polygon};
+
+	use super::DistanceField;
+	use crate::PolygonFastPrecalculator;
+
+	fn pixel_center(field: &DistanceField, x: usize, y: usize) -> Coordinate<f64> {
+		let rect = field.bounding_rect;
+		let (width, height) = (
+			rect.width() + 2. * field.spread,
+			rect.height() + 2. * field.spread,
+		);
+		Coordinate {
+			x: rect.min().x - field.spread + (x as f64 + 0.5) / field.width as f64 * width,
+			y: rect.max().y + field.spread - (y as f64 + 0.5) / field.height as f64 * height,
+		}
+	}
+
+	#[test]
+	fn square_with_hole() {
+		let polygon = MultiPolygon(vec![Polygon::new(
+			LineString::from(vec![(0., 0.), (4., 0.), (4., 4.), (0., 4.)]),
+			vec![LineString::from(vec![
+				(1., 1.),
+				(1., 3.),
+				(3., 3.),
+				(3., 1.),
+			])],
+		)]);
+		let tree = PolygonFastPrecalculator::calc("square".to_owned(), polygon.clone());
+		let field = tree.distance_field(&polygon, 64, 0.25);
+		assert_eq!((field.width, field.height), (64, 64));
+		assert_eq!(field.spread, 1.);
+
+		// Half of the range is zero distance, so sign of the distance is the side of 128
+		for y in 0..field.height {
+			for x in 0..field.width {
+				let value = field.data[y * field.width + x];
+				if tree.is_inside(Point(pixel_center(&field, x, y))) {
+					assert!(value >= 128, "{} {}: {}", x, y, value);
+				} else {
+					assert!(value <= 128, "{} {}: {}", x, y, value);
+				}
+			}
+		}
+
+		// Pixel is `6 / 64` wide, its center is at most half of its diagonal from the boundary point in it
+		let max_difference = (0.5 * 2f64.sqrt() * 6. / 64. / field.spread * 127.5).ceil() as i32;
+		for &(x, y) in &[(0., 2.), (4., 1.5), (2., 1.), (3., 2.5)] {
+			let column = ((x + 1.) / 6. * 64.) as usize;
+			let row = ((5. - y) / 6. * 64.) as usize;
+			let value = field.data[row * field.width + column] as i32;
+			assert!(
+				(value - 128).abs() <= max_difference,
+				"{} {}: {}",
+				x,
+				y,
+				value
+			);
+		}
+		// Clamped far from the boundary
+		assert_eq!(field.data[0], 0);
+	}
+
+	#[test]
+	#[should_panic(expected = "spread")]
+	fn zero_spread() {
+		let polygon = MultiPolygon(vec![Polygon::new(
+			LineString::from(vec![(0., 0.), (1., 0.), (0., 1.)]),
+			vec![],
+		)]);
+		let tree = PolygonFastPrecalculator::calc("triangle".to_owned(), polygon.clone());
+		tree.distance_field(&polygon, 16, 0.);
+	}
+}
@@ -0,0 +1,147 @@
+use std::fmt;
+
+use geo::Coordinate;
+
+use crate::{PolygonFastPrecalculator, PolygonFastPrecalculatorPart};
+
+#[derive(Clone, Debug, Default)]
+pub struct TreeStats {
+	pub nodes: usize,
+	pub splits: usize,
+	pub triangle_leaves: usize,
+	pub edge_leaves: usize,
+	pub empty_leaves: usize,
+	/// Depth of leaves, root has depth 0.
+	pub max_depth: usize,
+	pub min_depth: usize,
+	pub average_depth: f64,
+	/// Count of line checks for a point uniformly distributed in the bounding rect, triangle leaves are counted as 3 checks and edge leaves as 3 checks per edge, the same as `PolygonFastPrecalculatorPart::cost`.
+	pub expected_comparisons: f64,
+	/// Length of the generated code in bytes.
+	pub source_size: usize,
+}
+
+impl fmt::Display for TreeStats {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		writeln!(
+			f,
+			"nodes: {} ({} splits, {} triangle leaves, {} edge leaves, {} empty leaves)",
+			self.nodes, self.splits, self.triangle_leaves, self.edge_leaves, self.empty_leaves
+		)?;
+		writeln!(
+			f,
+			"leaf depth: min {}, max {}, average {:.2}",
+			self.min_depth, self.max_depth, self.average_depth
+		)?;
+		writeln!(f, "expected comparisons: {:.2}", self.expected_comparisons)?;
+		writeln!(f, "source size: {} bytes", self.source_size)
+	}
+}
+
+impl PolygonFastPrecalculator {
+	pub fn stats(&self) -> TreeStats {
+		let mut stats = TreeStats {
+			min_depth: usize::MAX,
+			..TreeStats::default()
+		};
+		let mut depth_sum = 0;
+		for cell in self.cells() {
+			stats.nodes += 1;
+			let checks = match cell.part {
+				PolygonFastPrecalculatorPart::LineSplit { .. } => {
+					stats.splits += 1;
+					continue;
+				},
+				PolygonFastPrecalculatorPart::Triangle { .. } => {
+					stats.triangle_leaves += 1;
+					3
+				},
+				PolygonFastPrecalculatorPart::Edges { edges } => {
+					stats.edge_leaves += 1;
+					3 * edges.len()
+				},
+				PolygonFastPrecalculatorPart::None => {
+					stats.empty_leaves += 1;
+					0
+				},
+			};
+			stats.max_depth = stats.max_depth.max(cell.depth);
+			stats.min_depth = stats.min_depth.min(cell.depth);
+			depth_sum += cell.depth;
+			// Cells are in `[0, 1]²`, so area is already the probability to get into the cell
+			stats.expected_comparisons += area(&cell.normalized) * (cell.depth + checks) as f64;
+		}
+		let leaves = stats.triangle_leaves + stats.edge_leaves + stats.empty_leaves;
+		stats.average_depth = depth_sum as f64 / leaves as f64;
+		stats.source_size = self.to_string().len();
+		stats
+	}
+}
+
+fn area(polygon: &[Coordinate<f64>]) -> f64 {
+	let mut result = 0.;
+	for (i, a) in polygon.iter().enumerate() {
+		let b = polygon[(i + 1) % polygon.len()];
+		result += a.x * b.y - b.x * a.y;
+	}
+	result.abs() / 2.
+}
+
+#[cfg(test)]
+mod tests {
+	use geo::{Coordinate, Line, Rect};
+
+	use crate::{
+		CrossingEdge, FillRule, LineSplitCheck, LineSplitCheckGeneralized,
+		PolygonFastPrecalculator, PolygonFastPrecalculatorPart,
+	};
+
+	#[test]
+	fn split_square() {
+		use PolygonFastPrecalculatorPart::*;
+
+		// `x < 0.25` is a triangle, the rest is split by `y < 0.5` into two edges and nothing
+		let edge = |x1: f64, y1: f64, x2: f64, y2: f64| {
+			CrossingEdge::calc(Line::new((x1, y1), (x2, y2))).unwrap()
+		};
+		let tree = PolygonFastPrecalculator {
+			name: "test".to_owned(),
+			bounding_rect: Rect::new(Coordinate { x: 0., y: 0. }, Coordinate { x: 2., y: 2. }),
+			fill_rule: FillRule::NonZero,
+			parts: LineSplit {
+				check: LineSplitCheck::MulToY { k: 0., b: 0.25 },
+				less: Box::new(Triangle {
+					checks: [
+						LineSplitCheckGeneralized::Greater(LineSplitCheck::MulToX { k: 0., b: 0. }),
+						LineSplitCheckGeneralized::Greater(LineSplitCheck::MulToY { k: 0., b: 0. }),
+						LineSplitCheckGeneralized::Less(LineSplitCheck::MulToX { k: -1., b: 0.25 }),
+					],
+				}),
+				greater: Box::new(LineSplit {
+					check: LineSplitCheck::MulToX { k: 0., b: 0.5 },
+					less: Box::new(Edges {
+						edges: vec![edge(0.5, 0., 0.5, 0.5), edge(0.75, 0.5, 0.75, 0.)],
+					}),
+					greater: Box::new(None),
+				}),
+			},
+		};
+
+		let stats = tree.stats();
+		assert_eq!(stats.nodes, 5);
+		assert_eq!(stats.splits, 2);
+		assert_eq!(stats.triangle_leaves, 1);
+		assert_eq!(stats.edge_leaves, 1);
+		assert_eq!(stats.empty_leaves, 1);
+		assert_eq!(stats.min_depth, 1);
+		assert_eq!(stats.max_depth, 2);
+		assert!((stats.average_depth - 5. / 3.).abs() < 1e-12);
+		// 0.25 * (1 + 3) + 0.375 * (2 + 3 * 2) + 0.375 * (2 + 0)
+		assert!(
+			(stats.expected_comparisons - 4.75).abs() < 1e-12,
+			"{}",
+			stats.expected_comparisons
+		);
+		assert_eq!(stats.source_size, tree.to_string().len());
+	}
+}
@@ -0,0 +1,460 @@
+use std::{f64::consts::PI, fmt, fs, path::Path};
+
+use geo::{Coordinate, LineString, MultiPolygon};
+use svgtypes::{PathParser, PathSegment, Transform};
+
+use crate::{
+	flatten::{flatten_cubic, flatten_quadratic},
+	rings_to_multipolygon,
+};
+
+#[derive(Debug)]
+pub enum SvgError {
+	Io(std::io::Error),
+	Xml(roxmltree::Error),
+	Path(svgtypes::Error),
+	Transform(svgtypes::Error),
+}
+
+impl fmt::Display for SvgError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		use SvgError::*;
+		match self {
+			Io(err) => write!(f, "can't read svg file: {}", err),
+			Xml(err) => write!(f, "can't parse svg file: {}", err),
+			Path(err) => write!(f, "can't parse path data: {}", err),
+			Transform(err) => write!(f, "can't parse transform: {}", err),
+		}
+	}
+}
+
+impl std::error::Error for SvgError {}
+
+/// Converts SVG path data to polygon, curves are flattened so they deviate from the original by at most `tolerance`. Every subpath becomes a ring, nested rings alternate between polygons and holes. Coordinates are the same as in SVG, so y axis points down.
+pub fn from_svg_path(d: &str, tolerance: f64) -> Result<MultiPolygon<f64>, SvgError> {
+	Ok(rings_to_multipolygon(flatten_path(
+		d,
+		&Transform::default(),
+		tolerance,
+	)?))
+}
+
+/// Reads SVG file, see `from_svg`.
+pub fn from_svg_file<P: AsRef<Path>>(
+	path: P,
+	tolerance: f64,
+) -> Result<MultiPolygon<f64>, SvgError> {
+	let text = fs::read_to_string(path).map_err(SvgError::Io)?;
+	from_svg(&text, tolerance)
+}
+
+/// Collects all rendered `<path>` elements of SVG document, with transforms of the element and all its groups applied. Paths inside of `<defs>`, `<clipPath>`, `<mask>` and `<symbol>` are not drawn by themselves, so they are skipped.
+pub fn from_svg(text: &str, tolerance: f64) -> Result<MultiPolygon<f64>, SvgError> {
+	const NOT_RENDERED: [&str; 4] = ["defs", "clipPath", "mask", "symbol"];
+
+	let document = roxmltree::Document::parse(text).map_err(SvgError::Xml)?;
+
+	let mut rings = Vec::new();
+	for node in document.descendants().filter(|node| {
+		node.is_element()
+			&& node.tag_name().name() == "path"
+			&& !node
+				.ancestors()
+				.any(|ancestor| NOT_RENDERED.contains(&ancestor.tag_name().name()))
+	}) {
+		let d = match node.attribute("d") {
+			Some(d) => d,
+			None => continue,
+		};
+
+		// Element itself goes first, then its groups from the nearest one
+		let mut transform = Transform::default();
+		for ancestor in node.ancestors() {
+			if let Some(text) = ancestor.attribute("transform") {
+				let current = text.parse::<Transform>().map_err(SvgError::Transform)?;
+				transform = multiply(&current, &transform);
+			}
+		}
+
+		rings.extend(flatten_path(d, &transform, tolerance)?);
+	}
+
+	Ok(rings_to_multipolygon(rings))
+}
+
+fn flatten_path(
+	d: &str,
+	transform: &Transform,
+	tolerance: f64,
+) -> Result<Vec<LineString<f64>>, SvgError> {
+	// Curves are flattened before transform, so tolerance is scaled to local coordinates by the largest singular value of the transform, that is the largest stretch of any direction
+	let t = transform;
+	let sum = t.a * t.a + t.b * t.b + t.c * t.c + t.d * t.d;
+	let determinant = t.a * t.d - t.b * t.c;
+	let scale = ((sum + (sum * sum - 4. * determinant * determinant).max(0.).sqrt()) / 2.).sqrt();
+	let tolerance = if scale > 0. {
+		tolerance / scale
+	} else {
+		tolerance
+	};
+
+	let mut rings = Vec::new();
+	let mut ring: Vec<(f64, f64)> = Vec::new();
+	let mut current = (0., 0.);
+	let mut start = (0., 0.);
+	// Second control point of previous curve, for smooth curves
+	let mut previous_control: Option<(f64, f64)> = None;
+
+	let finish = |ring: &mut Vec<(f64, f64)>, rings: &mut Vec<LineString<f64>>| {
+		if ring.len() >= 3 {
+			rings.push(LineString(
+				ring.iter()
+					.map(|&(x, y)| Coordinate {
+						x: transform.a * x + transform.c * y + transform.e,
+						y: transform.b * x + transform.d * y + transform.f,
+					})
+					.collect(),
+			));
+		}
+		ring.clear();
+	};
+
+	for segment in PathParser::from(d) {
+		let segment = segment.map_err(SvgError::Path)?;
+		let relative = |abs: bool, x: f64, y: f64| {
+			if abs {
+				(x, y)
+			} else {
+				(current.0 + x, current.1 + y)
+			}
+		};
+
+		// Subpath after `Z` without `M` starts at the start of previous one
+		if ring.is_empty()
+			&& !matches!(
+				segment,
+				PathSegment::MoveTo { .. } | PathSegment::ClosePath { .. }
+			) {
+			ring.push(current);
+		}
+
+		let mut control = None;
+		match segment {
+			PathSegment::MoveTo { abs, x, y } => {
+				finish(&mut ring, &mut rings);
+				current = relative(abs, x, y);
+				start = current;
+				ring.push(current);
+			},
+			PathSegment::LineTo { abs, x, y } => {
+				current = relative(abs, x, y);
+				ring.push(current);
+			},
+			PathSegment::HorizontalLineTo { abs, x } => {
+				current = (if abs { x } else { current.0 + x }, current.1);
+				ring.push(current);
+			},
+			PathSegment::VerticalLineTo { abs, y } => {
+				current = (current.0, if abs { y } else { current.1 + y });
+				ring.push(current);
+			},
+			PathSegment::CurveTo {
+				abs,
+				x1,
+				y1,
+				x2,
+				y2,
+				x,
+				y,
+			} => {
+				let (p1, p2, p3) = (
+					relative(abs, x1, y1),
+					relative(abs, x2, y2),
+					relative(abs, x, y),
+				);
+				flatten_cubic(current, p1, p2, p3, tolerance, &mut ring);
+				control = Some(p2);
+				current = p3;
+			},
+			PathSegment::SmoothCurveTo { abs, x2, y2, x, y } => {
+				let p1 = reflect(previous_control, current);
+				let (p2, p3) = (relative(abs, x2, y2), relative(abs, x, y));
+				flatten_cubic(current, p1, p2, p3, tolerance, &mut ring);
+				control = Some(p2);
+				current = p3;
+			},
+			PathSegment::Quadratic { abs, x1, y1, x, y } => {
+				let (p1, p2) = (relative(abs, x1, y1), relative(abs, x, y));
+				flatten_quadratic(current, p1, p2, tolerance, &mut ring);
+				control = Some(p1);
+				current = p2;
+			},
+			PathSegment::SmoothQuadratic { abs, x, y } => {
+				let p1 = reflect(previous_control, current);
+				let p2 = relative(abs, x, y);
+				flatten_quadratic(current, p1, p2, tolerance, &mut ring);
+				control = Some(p1);
+				current = p2;
+			},
+			PathSegment::EllipticalArc {
+				abs,
+				rx,
+				ry,
+				x_axis_rotation,
+				large_arc,
+				sweep,
+				x,
+				y,
+			} => {
+				let end = relative(abs, x, y);
+				flatten_arc(
+					current,
+					(rx, ry),
+					x_axis_rotation,
+					large_arc,
+					sweep,
+					end,
+					tolerance,
+					&mut ring,
+				);
+				current = end;
+			},
+			PathSegment::ClosePath { .. } => {
+				finish(&mut ring, &mut rings);
+				current = start;
+			},
+		}
+		// Smooth curves after other commands use current point as control point
+		previous_control = control;
+	}
+	finish(&mut ring, &mut rings);
+
+	Ok(rings)
+}
+
+fn reflect(control: Option<(f64, f64)>, current: (f64, f64)) -> (f64, f64) {
+	match control {
+		Some((x, y)) => (2. * current.0 - x, 2. * current.1 - y),
+		None => current,
+	}
+}
+
+// Conversion from endpoint to center parameterization is from SVG specification, appendix F.6.5
+#[allow(clippy::too_many_arguments)]
+fn flatten_arc(
+	start: (f64, f64),
+	radius: (f64, f64),
+	x_axis_rotation: f64,
+	large_arc: bool,
+	sweep: bool,
+	end: (f64, f64),
+	tolerance: f64,
+	ring: &mut Vec<(f64, f64)>,
+) {
+	let (mut rx, mut ry) = (radius.0.abs(), radius.1.abs());
+	if rx == 0. || ry == 0. || start == end {
+		ring.push(end);
+		return;
+	}
+
+	let phi = x_axis_rotation * PI / 180.;
+	let (sin, cos) = phi.sin_cos();
+	let dx = (start.0 - end.0) / 2.;
+	let dy = (start.1 - end.1) / 2.;
+	let x1 = cos * dx + sin * dy;
+	let y1 = -sin * dx + cos * dy;
+
+	// Radii are too small, scale them up
+	let lambda = x1 * x1 / (rx * rx) + y1 * y1 / (ry * ry);
+	if lambda > 1. {
+		rx *= lambda.sqrt();
+		ry *= lambda.sqrt();
+	}
+
+	let numerator = rx * rx * ry * ry - rx * rx * y1 * y1 - ry * ry * x1 * x1;
+	let denominator = rx * rx * y1 * y1 + ry * ry * x1 * x1;
+	let mut coef = (numerator / denominator).max(0.).sqrt();
+	if large_arc == sweep {
+		coef = -coef;
+	}
+	let cx1 = coef * rx * y1 / ry;
+	let cy1 = -coef * ry * x1 / rx;
+	let cx = cos * cx1 - sin * cy1 + (start.0 + end.0) / 2.;
+	let cy = sin * cx1 + cos * cy1 + (start.1 + end.1) / 2.;
+
+	let angle = |ux: f64, uy: f64| uy.atan2(ux);
+	let theta = angle((x1 - cx1) / rx, (y1 - cy1) / ry);
+	let mut delta = angle((-x1 - cx1) / rx, (-y1 - cy1) / ry) - theta;
+	if sweep && delta < 0. {
+		delta += 2. * PI;
+	} else if !sweep && delta > 0. {
+		delta -= 2. * PI;
+	}
+
+	// Maximal angle step, so chord deviates from the arc by at most tolerance
+	let r = rx.max(ry);
+	let step = if tolerance < r {
+		2. * (1. - tolerance / r).acos()
+	} else {
+		PI / 2.
+	};
+	let count = ((delta.abs() / step).ceil() as usize).max(1).min(1000);
+	for i in 1..count {
+		let t = theta + delta * i as f64 / count as f64;
+		let (x, y) = (rx * t.cos(), ry * t.sin());
+		ring.push((cos * x - sin * y + cx, sin * x + cos * y + cy));
+	}
+	ring.push(end);
+}
+
+// Transform that applies `b` and then `a`
+fn multiply(a: &Transform, b: &Transform) -> Transform {
+	Transform::new(
+		a.a * b.a + a.c * b.b,
+		a.b * b.a + a.d * b.b,
+		a.a * b.c + a.c * b.d,
+		a.b * b.c + a.d * b.d,
+		a.a * b.e + a.c * b.f + a.e,
+		a.b * b.e + a.d * b.f + a.f,
+	)
+}
+
+#[cfg(test)]
+mod tests {
+	use geo::{prelude::BoundingRect, LineString, MultiPolygon};
+	use svgtypes::Transform;
+
+	use super::{flatten_path, from_svg, from_svg_path};
+	use crate::fill_rule::ring_signed_area;
+
+	fn area(polygon: &MultiPolygon<f64>) -> f64 {
+		polygon
+			.0
+			.iter()
+			.map(|poly| {
+				ring_signed_area(poly.exterior()).abs()
+					- poly
+						.interiors()
+						.iter()
+						.map(|ring| ring_signed_area(ring).abs())
+						.sum::<f64>()
+			})
+			.sum()
+	}
+
+	fn rings(polygon: &MultiPolygon<f64>) -> Vec<&LineString<f64>> {
+		polygon.0.iter().map(|poly| poly.exterior()).collect()
+	}
+
+	#[test]
+	fn relative_commands() {
+		let absolute = from_svg_path("M 1 1 L 3 1 L 3 3 Z M 5 5 L 6 5 L 6 6 Z", 0.01).unwrap();
+		let relative = from_svg_path("m 1 1 l 2 0 l 0 2 z m 4 4 l 1 0 l 0 1 z", 0.01).unwrap();
+		assert_eq!(rings(&relative), rings(&absolute));
+
+		// Subpath after `z` without `m` starts at the start of previous one
+		let reopened = flatten_path(
+			"M 1 1 L 3 1 L 3 3 Z l 0 2 l -1 -1 Z",
+			&Transform::default(),
+			0.01,
+		)
+		.unwrap();
+		assert_eq!(reopened.len(), 2);
+		assert_eq!(
+			reopened[1],
+			LineString::from(vec![(1., 1.), (1., 3.), (0., 2.)])
+		);
+	}
+
+	#[test]
+	fn horizontal_and_vertical_lines() {
+		let polygon = from_svg_path("M 0 0 H 4 V 3 h -4 v -3 z", 0.01).unwrap();
+		assert_eq!(area(&polygon), 12.);
+	}
+
+	#[test]
+	fn smooth_curves() {
+		// Reflected control points make the second hump go down, so humps cancel each other
+		for d in &[
+			"M 0 0 C 0 2 2 2 2 0 S 4 -2 4 0 L 4 -3 L 0 -3 Z",
+			"M 0 0 Q 1 2 2 0 T 4 0 L 4 -3 L 0 -3 Z",
+		] {
+			let polygon = from_svg_path(d, 0.001).unwrap();
+			assert!(
+				(area(&polygon) - 12.).abs() < 0.01,
+				"{}: {}",
+				d,
+				area(&polygon)
+			);
+		}
+
+		// After a line there is no control point to reflect
+		let polygon = from_svg_path("M 0 0 L 2 0 T 4 0 L 4 -3 L 0 -3 Z", 0.001).unwrap();
+		assert_eq!(area(&polygon), 12.);
+	}
+
+	#[test]
+	fn arcs() {
+		let circle = from_svg_path("M -1 0 A 1 1 0 0 0 1 0 A 1 1 0 0 0 -1 0 Z", 0.001).unwrap();
+		assert!((area(&circle) - std::f64::consts::PI).abs() < 0.01);
+
+		// Large arc of the same circle, and radius that is too small is scaled up
+		for d in &[
+			"M 0 -1 A 1 1 0 1 1 -1 0 L 0 0 Z",
+			"M 0 -1 a 1 1 0 1 1 -1 1 l 1 0 z",
+		] {
+			let polygon = from_svg_path(d, 0.001).unwrap();
+			let expected = 0.75 * std::f64::consts::PI;
+			assert!((area(&polygon) - expected).abs() < 0.01, "{}", d);
+		}
+		let half = from_svg_path("M 0 0 A 0.5 0.5 0 0 1 2 0 Z", 0.001).unwrap();
+		assert!((area(&half) - std::f64::consts::PI / 2.).abs() < 0.01);
+	}
+
+	#[test]
+	fn nested_transforms() {
+		let svg = r#"<svg xmlns="http://www.w3.org/2000/svg">
+			<g transform="translate(10 0)">
+				<g transform="scale(2)">
+					<path d="M 0 0 H 1 V 1 H 0 Z"/>
+					<path transform="translate(0 3)" d="M 0 0 H 1 V 1 H 0 Z"/>
+				</g>
+			</g>
+			<defs><path id="a" d="M 100 100 h 1 v 1 h -1 z"/></defs>
+			<clipPath><path d="M 100 100 h 1 v 1 h -1 z"/></clipPath>
+			<mask><path d="M 100 100 h 1 v 1 h -1 z"/></mask>
+			<symbol><path d="M 100 100 h 1 v 1 h -1 z"/></symbol>
+		</svg>"#;
+		let polygon = from_svg(svg, 0.01).unwrap();
+		assert_eq!(polygon.0.len(), 2);
+		assert_eq!(area(&polygon), 8.);
+		let rect = polygon.bounding_rect().unwrap();
+		assert_eq!((rect.min().x, rect.min().y), (10., 0.));
+		assert_eq!((rect.max().x, rect.max().y), (12., 8.));
+	}
+
+	#[test]
+	fn tolerance_of_stretched_curves() {
+		let svg = r#"<svg xmlns="http://www.w3.org/2000/svg">
+			<path transform="scale(1 100)" d="M -1 0 A 1 1 0 0 0 1 0 A 1 1 0 0 0 -1 0 Z"/>
+		</svg>"#;
+		let tolerance = 0.1;
+		let polygon = from_svg(svg, tolerance).unwrap();
+		let points = &polygon.0[0].exterior().0;
+		// Middle of every chord is compared with the point of the ellipse at the middle angle
+		for pair in points.windows(2) {
+			let (a, b) = (pair[0], pair[1]);
+			let (ta, tb) = ((a.y / 100.).atan2(a.x), (b.y / 100.).atan2(b.x));
+			let mut delta = tb - ta;
+			if delta > std::f64::consts::PI {
+				delta -= 2. * std::f64::consts::PI;
+			} else if delta < -std::f64::consts::PI {
+				delta += 2. * std::f64::consts::PI;
+			}
+			let t = ta + delta / 2.;
+			let (x, y) = ((a.x + b.x) / 2., (a.y + b.y) / 2.);
+			let deviation = ((x - t.cos()).powi(2) + (y - 100. * t.sin()).powi(2)).sqrt();
+			assert!(deviation <= tolerance, "{:?} {:?}: {}", a, b, deviation);
+		}
+	}
+}
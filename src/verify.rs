@@ -0,0 +1,353 @@
+use std::fmt;
+
+use geo::{
+	prelude::{BoundingRect, Contains},
+	Coordinate, Line, MultiPolygon, Point,
+};
+use line_intersection::LineInterval;
+
+use crate::{
+	fill_rule::{distance_to_segment, FillRule},
+	PolygonFastPrecalculator,
+};
+
+#[derive(Clone, Debug, Default)]
+pub struct VerificationReport {
+	pub samples: usize,
+	pub mismatches: usize,
+	/// Distance from the polygon boundary of the most distant misclassified point, in coordinates of the original polygon.
+	pub max_mismatch_distance: f64,
+	pub mismatched_points: Vec<Point<f64>>,
+}
+
+impl VerificationReport {
+	pub fn is_ok(&self) -> bool {
+		self.mismatches == 0
+	}
+
+	/// Mismatches that lie closer than `tolerance` to the boundary are considered as rounding noise.
+	pub fn is_ok_with_tolerance(&self, tolerance: f64) -> bool {
+		self.max_mismatch_distance <= tolerance
+	}
+}
+
+impl fmt::Display for VerificationReport {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		writeln!(
+			f,
+			"mismatches: {} of {} samples",
+			self.mismatches, self.samples
+		)?;
+		writeln!(f, "max mismatch distance: {:e}", self.max_mismatch_distance)?;
+		for point in &self.mismatched_points {
+			writeln!(f, "  {} {}", point.x(), point.y())?;
+		}
+		Ok(())
+	}
+}
+
+impl PolygonFastPrecalculator {
+	/// Compares `is_inside` with `geo::Contains` on points from uniform grid, random points, and points jittered near the edges. `geo::Contains` knows nothing about fill rules, so it's used only for `EvenOdd` and `NonZero` on polygons without self-intersections, where they agree with it, otherwise `is_inside` is compared with the check of `self.fill_rule`.
+	pub fn verify(&self, original: &MultiPolygon<f64>, samples: usize) -> VerificationReport {
+		verify_with(original, self.fill_rule, samples, |point| {
+			self.is_inside(point)
+		})
+	}
+}
+
+// Shared by all precalculators, `is_inside` is compared with `fill_rule`
+pub(crate) fn verify_with(
+	original: &MultiPolygon<f64>,
+	fill_rule: FillRule,
+	samples: usize,
+	is_inside: impl Fn(Point<f64>) -> bool,
+) -> VerificationReport {
+	let edges = original
+		.0
+		.iter()
+		.map(|poly| std::iter::once(poly.exterior()).chain(poly.interiors().iter()))
+		.flatten()
+		.map(|ring| ring.lines())
+		.flatten()
+		.collect::<Vec<_>>();
+
+	let mut report = VerificationReport::default();
+	let br = match original.bounding_rect() {
+		Some(br) => br,
+		None => return report,
+	};
+	let (min, width, height) = (br.min(), br.width(), br.height());
+	let jitter = (width * width + height * height).sqrt() * 1e-3;
+
+	let mut points = Vec::with_capacity(samples);
+
+	// Uniform grid, slightly bigger than bounding rect to check borders too
+	let grid = ((samples / 3) as f64).sqrt() as usize;
+	for i in 0..grid {
+		for j in 0..grid {
+			let x = (i as f64 + 0.5) / grid as f64 * 1.1 - 0.05;
+			let y = (j as f64 + 0.5) / grid as f64 * 1.1 - 0.05;
+			points.push(Point::new(min.x + x * width, min.y + y * height));
+		}
+	}
+
+	let mut random = Random::new(samples as u64);
+	let near_edges = if edges.is_empty() {
+		0
+	} else {
+		(samples - points.len()) / 2
+	};
+
+	// Near edges
+	for _ in 0..near_edges {
+		let edge = edges[random.next_usize(edges.len())];
+		let t = random.next_f64();
+		let dx = (random.next_f64() * 2. - 1.) * jitter;
+		let dy = (random.next_f64() * 2. - 1.) * jitter;
+		points.push(Point::new(
+			edge.start.x + edge.dx() * t + dx,
+			edge.start.y + edge.dy() * t + dy,
+		));
+	}
+
+	// Random
+	while points.len() < samples {
+		let x = random.next_f64() * 1.1 - 0.05;
+		let y = random.next_f64() * 1.1 - 0.05;
+		points.push(Point::new(min.x + x * width, min.y + y * height));
+	}
+
+	let use_contains = matches!(fill_rule, FillRule::EvenOdd | FillRule::NonZero)
+		&& is_simple(&edges)
+		&& !has_nested_polygons(original);
+	let reference = |point: Point<f64>| {
+		if use_contains {
+			original.contains(&point)
+		} else {
+			fill_rule.contains(original, point)
+		}
+	};
+
+	report.samples = points.len();
+	for point in points {
+		if is_inside(point) != reference(point) {
+			let distance = distance_to_boundary(&edges, point.into());
+			report.mismatches += 1;
+			if distance > report.max_mismatch_distance {
+				report.max_mismatch_distance = distance;
+			}
+			report.mismatched_points.push(point);
+		}
+	}
+
+	report
+}
+
+// Exterior of some polygon is inside another polygon, `geo::Contains` takes their union while fill rules make a hole
+fn has_nested_polygons(polygon: &MultiPolygon<f64>) -> bool {
+	polygon.0.iter().enumerate().any(|(i, inner)| {
+		inner.exterior().0.first().map_or(false, |&first| {
+			polygon
+				.0
+				.iter()
+				.enumerate()
+				.any(|(j, outer)| i != j && outer.contains(&Point(first)))
+		})
+	})
+}
+
+// No two edges intersect except at shared vertices
+fn is_simple(edges: &[Line<f64>]) -> bool {
+	edges.iter().enumerate().all(|(i, a)| {
+		edges[i + 1..].iter().all(|b| {
+			let shared = [a.start, a.end]
+				.iter()
+				.any(|coord| *coord == b.start || *coord == b.end);
+			shared
+				|| LineInterval::line_segment(*a)
+					.relate(&LineInterval::line_segment(*b))
+					.unique_intersection()
+					.is_none()
+		})
+	})
+}
+
+fn distance_to_boundary(edges: &[Line<f64>], point: Coordinate<f64>) -> f64 {
+	edges
+		.iter()
+		.map(|edge| distance_to_segment(edge, point))
+		.fold(std::f64::INFINITY, f64::min)
+}
+
+/// Xorshift generator, so verification is reproducible and doesn't need `rand`.
+pub(crate) struct Random(u64);
+
+impl Random {
+	pub fn new(seed: u64) -> Self {
+		Self((seed ^ 0x9E37_79B9_7F4A_7C15) | 1)
+	}
+
+	pub fn next_u64(&mut self) -> u64 {
+		self.0 ^= self.0 << 13;
+		self.0 ^= self.0 >> 7;
+		self.0 ^= self.0 << 17;
+		self.0
+	}
+
+	pub fn next_f64(&mut self) -> f64 {
+		(self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+	}
+
+	pub fn next_usize(&mut self, max: usize) -> usize {
+		(self.next_u64() % max as u64) as usize
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use geo::{
+		prelude::{BoundingRect, Contains},
+		LineString, MultiPolygon, Point, Polygon,
+	};
+
+	use super::{has_nested_polygons, is_simple, verify_with, Random};
+	use crate::{edges, CalcOptions, FillRule, PolygonFastPrecalculator};
+
+	// Angles are jittered around uniform ones, so the origin sees every vertex and edges never intersect
+	fn random_polygon(random: &mut Random) -> MultiPolygon<f64> {
+		let count = 5 + random.next_usize(12);
+		let coords = (0..count)
+			.map(|i| {
+				let angle =
+					(i as f64 + 0.8 * random.next_f64()) / count as f64 * 2. * std::f64::consts::PI;
+				let radius = 0.3 + 0.7 * random.next_f64();
+				(radius * angle.cos(), radius * angle.sin())
+			})
+			.collect::<Vec<_>>();
+		MultiPolygon(vec![Polygon::new(LineString::from(coords), vec![])])
+	}
+
+	#[test]
+	fn random_polygons() {
+		let mut random = Random::new(27);
+		for _ in 0..30 {
+			let polygon = random_polygon(&mut random);
+			for &max_edges_per_leaf in &[0, 8] {
+				let options = CalcOptions {
+					max_edges_per_leaf,
+					..CalcOptions::default()
+				};
+				let tree = PolygonFastPrecalculator::calc_with_options(
+					"random".to_owned(),
+					polygon.clone(),
+					&options,
+				);
+				let report = tree.verify(&polygon, 2000);
+				// Simplification of the tree drops vertices with triangles smaller than `1e-4` of the bounding square, which moves edges by about `1e-3` of its side
+				let rect = polygon.bounding_rect().unwrap();
+				assert!(
+					report.is_ok_with_tolerance(0.002 * rect.width().max(rect.height())),
+					"{}\n{:?}",
+					report,
+					polygon
+				);
+			}
+		}
+	}
+
+	#[test]
+	fn detects_other_polygon() {
+		let mut random = Random::new(1);
+		let polygon = random_polygon(&mut random);
+		let tree = PolygonFastPrecalculator::calc("random".to_owned(), polygon);
+		let other = random_polygon(&mut random);
+		let report = tree.verify(&other, 2000);
+		assert!(!report.is_ok_with_tolerance(0.01));
+		assert_eq!(report.mismatches, report.mismatched_points.len());
+	}
+
+	#[test]
+	fn contains_is_reference() {
+		let pentagram = MultiPolygon(vec![Polygon::new(
+			LineString::from(vec![
+				(0., 1.),
+				(-0.5878, -0.809),
+				(0.9511, 0.309),
+				(-0.9511, 0.309),
+				(0.5878, -0.809),
+			]),
+			vec![],
+		)]);
+		let clockwise = MultiPolygon(vec![Polygon::new(
+			LineString::from(vec![(0., 0.), (0., 1.), (1., 1.), (1., 0.)]),
+			vec![],
+		)]);
+		let holed = MultiPolygon(vec![Polygon::new(
+			LineString::from(vec![(0., 0.), (4., 0.), (4., 4.), (0., 4.)]),
+			vec![LineString::from(vec![
+				(1., 1.),
+				(1., 3.),
+				(3., 3.),
+				(3., 1.),
+			])],
+		)]);
+		assert!(is_simple(&edges(&holed).collect::<Vec<_>>()));
+		assert!(!is_simple(&edges(&pentagram).collect::<Vec<_>>()));
+
+		for &fill_rule in &[FillRule::EvenOdd, FillRule::NonZero] {
+			let report = verify_with(&holed, fill_rule, 2000, |point| holed.contains(&point));
+			assert!(report.is_ok(), "{}", report);
+			let tree = PolygonFastPrecalculator::calc_with_options(
+				"holed".to_owned(),
+				holed.clone(),
+				&CalcOptions {
+					fill_rule,
+					..CalcOptions::default()
+				},
+			);
+			let report = tree.verify(&holed, 2000);
+			assert!(report.is_ok_with_tolerance(1e-9), "{}", report);
+		}
+
+		// Clockwise square is empty by the positive rule, so its check is used instead of `geo::Contains`
+		let report = verify_with(&clockwise, FillRule::Positive, 2000, |point| {
+			clockwise.contains(&point)
+		});
+		assert!(report.mismatches > 0);
+		let report = verify_with(&clockwise, FillRule::Positive, 2000, |_| false);
+		assert!(report.is_ok(), "{}", report);
+		let tree = PolygonFastPrecalculator::calc("pentagram".to_owned(), pentagram.clone());
+		assert!(tree.verify(&pentagram, 2000).is_ok_with_tolerance(1e-9));
+
+		// Contours of a glyph are separate polygons, the inner one is a hole by both rules
+		let contours = MultiPolygon(vec![
+			Polygon::new(
+				LineString::from(vec![(0., 0.), (4., 0.), (4., 4.), (0., 4.)]),
+				vec![],
+			),
+			Polygon::new(
+				LineString::from(vec![(1., 1.), (1., 3.), (3., 3.), (3., 1.)]),
+				vec![],
+			),
+		]);
+		assert!(has_nested_polygons(&contours));
+		assert!(!has_nested_polygons(&holed));
+		for &fill_rule in &[FillRule::EvenOdd, FillRule::NonZero] {
+			let report = verify_with(&contours, fill_rule, 2000, |point| {
+				contours.contains(&point)
+			});
+			assert!(report.mismatches > 0);
+			let tree = PolygonFastPrecalculator::calc_with_options(
+				"contours".to_owned(),
+				contours.clone(),
+				&CalcOptions {
+					fill_rule,
+					..CalcOptions::default()
+				},
+			);
+			assert!(!tree.is_inside(Point::new(2., 2.)));
+			let report = tree.verify(&contours, 2000);
+			assert!(report.is_ok_with_tolerance(1e-9), "{}", report);
+		}
+	}
+}
@@ -0,0 +1,281 @@
+use std::fmt::Write;
+
+#[cfg(feature = "debug-image")]
+use geo::prelude::BoundingRect;
+use geo::{Coordinate, Line, LineString, MultiPolygon, Point, Polygon};
+
+#[cfg(feature = "debug-image")]
+use crate::{fill_rule::ring_winding_number, image::Image};
+use crate::{Normalization, PolygonFastPrecalculator, PolygonFastPrecalculatorPart};
+
+const MARGIN: f64 = 10.;
+
+// Parts of the picture in coordinates of the original polygon
+struct Scene {
+	triangles: Vec<(Polygon<f64>, (u8, u8, u8))>,
+	splits: Vec<Line<f64>>,
+	// Edges of `Edges` leaves
+	edges: Vec<(Line<f64>, (u8, u8, u8))>,
+}
+
+impl PolygonFastPrecalculator {
+	fn scene(&self) -> Scene {
+		let cells = self.cells();
+		let max_depth = cells.iter().map(|cell| cell.depth).max().unwrap_or(0);
+		let mut scene = Scene {
+			triangles: Vec::new(),
+			splits: Vec::new(),
+			edges: Vec::new(),
+		};
+		for cell in cells {
+			match cell.part {
+				PolygonFastPrecalculatorPart::LineSplit { .. } => {
+					scene.splits.extend(cell.split_line(self));
+				},
+				PolygonFastPrecalculatorPart::Triangle { .. } => {
+					if let Some(triangle) = cell.triangle(self) {
+						scene
+							.triangles
+							.push((triangle, depth_color(cell.depth, max_depth)));
+					}
+				},
+				PolygonFastPrecalculatorPart::Edges { .. } => {
+					let color = depth_color(cell.depth, max_depth);
+					scene
+						.edges
+						.extend(cell.edges(self).into_iter().map(|line| (line, color)));
+				},
+				PolygonFastPrecalculatorPart::None => {},
+			}
+		}
+		scene
+	}
+
+	// Image size and transform from polygon coordinates to pixels, y axis points up as in the shader
+	fn picture_transform(
+		&self,
+		size: usize,
+	) -> ((usize, usize), impl Fn(Coordinate<f64>) -> (f64, f64)) {
+		let rect = self.bounding_rect;
+		let scale = size as f64 / rect.width().max(rect.height());
+		let (min, max) = (rect.min(), rect.max());
+		let width = (rect.width() * scale + 2. * MARGIN).ceil() as usize;
+		let height = (rect.height() * scale + 2. * MARGIN).ceil() as usize;
+		let transform = move |coord: Coordinate<f64>| {
+			(
+				MARGIN + (coord.x - min.x) * scale,
+				MARGIN + (max.y - coord.y) * scale,
+			)
+		};
+		((width, height), transform)
+	}
+
+	/// SVG picture with leaf triangles and edges of edge leaves colored by depth from blue to red, split lines clipped to their cells, outline of `original` polygon and `points`, e.g. mismatches of `verify`. Largest side of the picture is `size` pixels without margins.
+	pub fn to_svg(
+		&self,
+		original: Option<&MultiPolygon<f64>>,
+		points: &[Point<f64>],
+		size: usize,
+	) -> String {
+		let ((width, height), transform) = self.picture_transform(size);
+		let path = |ring: &LineString<f64>| {
+			let mut result = String::new();
+			for (i, coord) in ring.0.iter().enumerate() {
+				let (x, y) = transform(*coord);
+				let command = if i == 0 { 'M' } else { 'L' };
+				write!(result, "{}{:.2} {:.2} ", command, x, y).unwrap();
+			}
+			result.push('Z');
+			result
+		};
+
+		let scene = self.scene();
+		let mut result = String::new();
+		writeln!(
+			result,
+			r#"<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="{}">"#,
+			width, height
+		)
+		.unwrap();
+		writeln!(result, r#"<rect width="100%" height="100%" fill="white"/>"#).unwrap();
+		for (triangle, (r, g, b)) in &scene.triangles {
+			writeln!(
+				result,
+				r#"<path d="{}" fill="rgb({},{},{})"/>"#,
+				path(triangle.exterior()),
+				r,
+				g,
+				b
+			)
+			.unwrap();
+		}
+		for line in &scene.splits {
+			let ((x1, y1), (x2, y2)) = (transform(line.start), transform(line.end));
+			writeln!(
+				result,
+				r#"<line x1="{:.2}" y1="{:.2}" x2="{:.2}" y2="{:.2}" stroke="gray"/>"#,
+				x1, y1, x2, y2
+			)
+			.unwrap();
+		}
+		for (line, (r, g, b)) in &scene.edges {
+			let ((x1, y1), (x2, y2)) = (transform(line.start), transform(line.end));
+			writeln!(
+				result,
+				r#"<line x1="{:.2}" y1="{:.2}" x2="{:.2}" y2="{:.2}" stroke="rgb({},{},{})" stroke-width="3"/>"#,
+				x1, y1, x2, y2, r, g, b
+			)
+			.unwrap();
+		}
+		if let Some(original) = original {
+			let d = original
+				.0
+				.iter()
+				.map(|poly| std::iter::once(poly.exterior()).chain(poly.interiors().iter()))
+				.flatten()
+				.map(path)
+				.collect::<Vec<_>>()
+				.join(" ");
+			writeln!(
+				result,
+				r#"<path d="{}" fill="none" stroke="black" stroke-width="1.5"/>"#,
+				d
+			)
+			.unwrap();
+		}
+		for point in points {
+			let (x, y) = transform(point.0);
+			writeln!(
+				result,
+				r#"<circle cx="{:.2}" cy="{:.2}" r="2" fill="red"/>"#,
+				x, y
+			)
+			.unwrap();
+		}
+		result.push_str("</svg>\n");
+		result
+	}
+
+	/// Same picture as `to_svg`, but rasterized.
+	#[cfg(feature = "debug-image")]
+	pub fn to_image(
+		&self,
+		original: Option<&MultiPolygon<f64>>,
+		points: &[Point<f64>],
+		size: usize,
+	) -> Image {
+		let ((width, height), transform) = self.picture_transform(size);
+		let mut image = Image::new(width, height);
+		image.fill((255, 255, 255));
+
+		let scene = self.scene();
+		let ring_to_pixels = |ring: &LineString<f64>| {
+			LineString(
+				ring.0
+					.iter()
+					.map(|coord| {
+						let (x, y) = transform(*coord);
+						Coordinate { x, y }
+					})
+					.collect(),
+			)
+		};
+		for (triangle, color) in &scene.triangles {
+			let ring = ring_to_pixels(triangle.exterior());
+			let rect = match ring.bounding_rect() {
+				Some(rect) => rect,
+				None => continue,
+			};
+			let (min_x, min_y) = (rect.min().x.max(0.) as usize, rect.min().y.max(0.) as usize);
+			let max_x = (rect.max().x.ceil() as usize).min(width);
+			let max_y = (rect.max().y.ceil() as usize).min(height);
+			for y in min_y..max_y {
+				for x in min_x..max_x {
+					let center = Point::new(x as f64 + 0.5, y as f64 + 0.5);
+					if ring_winding_number(&ring, center) != 0 {
+						image.set_pixel(x, y, *color);
+					}
+				}
+			}
+		}
+		for line in &scene.splits {
+			image.draw_line(transform(line.start), transform(line.end), (128, 128, 128));
+		}
+		for (line, color) in &scene.edges {
+			image.draw_line(transform(line.start), transform(line.end), *color);
+		}
+		if let Some(original) = original {
+			for line in original
+				.0
+				.iter()
+				.map(|poly| std::iter::once(poly.exterior()).chain(poly.interiors().iter()))
+				.flatten()
+				.map(|ring| ring.lines())
+				.flatten()
+			{
+				image.draw_line(transform(line.start), transform(line.end), (0, 0, 0));
+			}
+		}
+		for point in points {
+			image.draw_point(transform(point.0), 2, (255, 0, 0));
+		}
+		image
+	}
+
+	/// GLSL function `vec2 leaf_<name>(vec2 a)` with the same splits as `is_inside_<name>`, it returns index of the reached leaf in prefix order and depth of this leaf, or `vec2(-1., 0.)` outside of the bounding rect. It's used to color fragments by leaves in the demo.
+	pub fn leaf_code(&self) -> String {
+		fn leaf_inner(
+			part: &PolygonFastPrecalculatorPart,
+			depth: usize,
+			leaf: &mut usize,
+			result: &mut String,
+		) {
+			let tab = " ".repeat(2 * depth + 4);
+			match part {
+				PolygonFastPrecalculatorPart::LineSplit {
+					check,
+					less,
+					greater,
+				} => {
+					writeln!(result, "{}if ({}) {{", tab, check).unwrap();
+					leaf_inner(less, depth + 1, leaf, result);
+					writeln!(result, "{}}} else {{", tab).unwrap();
+					leaf_inner(greater, depth + 1, leaf, result);
+					writeln!(result, "{}}}", tab).unwrap();
+				},
+				_ => {
+					writeln!(result, "{}return vec2({}., {}.);", tab, leaf, depth).unwrap();
+					*leaf += 1;
+				},
+			}
+		}
+
+		let mut result = String::new();
+		writeln!(result, "vec2 leaf_{}(vec2 a) {{", self.name).unwrap();
+		writeln!(result, "  {}", Normalization(&self.bounding_rect)).unwrap();
+		writeln!(
+			result,
+			"  if (0. <= a.x && a.x <= 1. && 0. <= a.y && a.y <= 1.) {{"
+		)
+		.unwrap();
+		leaf_inner(&self.parts, 0, &mut 0, &mut result);
+		writeln!(result, "  }} else {{").unwrap();
+		writeln!(result, "    return vec2(-1., 0.);").unwrap();
+		writeln!(result, "  }}").unwrap();
+		writeln!(result, "}}").unwrap();
+		result
+	}
+}
+
+fn depth_color(depth: usize, max_depth: usize) -> (u8, u8, u8) {
+	let t = if max_depth == 0 {
+		0.
+	} else {
+		depth as f64 / max_depth as f64
+	};
+	(
+		(60. + 180. * t) as u8,
+		(120. - 40. * t) as u8,
+		(240. - 180. * t) as u8,
+	)
+}
@@ -0,0 +1,280 @@
+use std::fmt;
+
+use geo::{Coordinate, LineString, MultiPolygon, Polygon};
+
+/// Error with byte offset in the parsed text.
+#[derive(Clone, Debug, PartialEq)]
+pub struct WktError {
+	pub position: usize,
+	pub message: String,
+}
+
+impl fmt::Display for WktError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "{} at position {}", self.message, self.position)
+	}
+}
+
+impl std::error::Error for WktError {}
+
+/// Reads `POLYGON`, `MULTIPOLYGON` or `GEOMETRYCOLLECTION` of them. First ring of every polygon is exterior, others are holes.
+pub fn from_wkt(s: &str) -> Result<MultiPolygon<f64>, WktError> {
+	let mut parser = Parser { s, pos: 0 };
+	let mut result = Vec::new();
+	parser.geometry(&mut result)?;
+	parser.skip_whitespace();
+	if parser.pos != s.len() {
+		return Err(parser.error("unexpected text after geometry"));
+	}
+	Ok(MultiPolygon(result))
+}
+
+pub fn to_wkt(polygon: &MultiPolygon<f64>) -> String {
+	fn ring(ring: &LineString<f64>) -> String {
+		let points = ring
+			.0
+			.iter()
+			.map(|coord| format!("{} {}", coord.x, coord.y))
+			.collect::<Vec<_>>();
+		format!("({})", points.join(", "))
+	}
+
+	if polygon.0.is_empty() {
+		return "MULTIPOLYGON EMPTY".to_owned();
+	}
+	let polygons = polygon
+		.0
+		.iter()
+		.map(|poly| {
+			let rings = std::iter::once(poly.exterior())
+				.chain(poly.interiors().iter())
+				.map(ring)
+				.collect::<Vec<_>>();
+			format!("({})", rings.join(", "))
+		})
+		.collect::<Vec<_>>();
+	format!("MULTIPOLYGON ({})", polygons.join(", "))
+}
+
+struct Parser<'a> {
+	s: &'a str,
+	pos: usize,
+}
+
+impl<'a> Parser<'a> {
+	fn error(&self, message: &str) -> WktError {
+		WktError {
+			position: self.pos,
+			message: message.to_owned(),
+		}
+	}
+
+	fn skip_whitespace(&mut self) {
+		let rest = &self.s[self.pos..];
+		self.pos += rest.len() - rest.trim_start().len();
+	}
+
+	fn take_while(&mut self, f: impl Fn(char) -> bool) -> &'a str {
+		self.skip_whitespace();
+		let s = self.s;
+		let rest = &s[self.pos..];
+		let len = rest.find(|c| !f(c)).unwrap_or_else(|| rest.len());
+		self.pos += len;
+		&rest[..len]
+	}
+
+	fn keyword(&mut self) -> String {
+		self.take_while(|c| c.is_ascii_alphabetic())
+			.to_ascii_uppercase()
+	}
+
+	fn consume(&mut self, c: char) -> bool {
+		self.skip_whitespace();
+		if self.s[self.pos..].starts_with(c) {
+			self.pos += c.len_utf8();
+			true
+		} else {
+			false
+		}
+	}
+
+	fn expect(&mut self, c: char) -> Result<(), WktError> {
+		if self.consume(c) {
+			Ok(())
+		} else {
+			Err(self.error(&format!("expected `{}`", c)))
+		}
+	}
+
+	// Parses `(` item, item, ... `)` or `EMPTY`
+	fn list<T>(
+		&mut self,
+		mut item: impl FnMut(&mut Self) -> Result<T, WktError>,
+	) -> Result<Vec<T>, WktError> {
+		let start = self.pos;
+		if self.keyword() == "EMPTY" {
+			return Ok(Vec::new());
+		}
+		self.pos = start;
+
+		self.expect('(')?;
+		let mut result = vec![item(self)?];
+		while self.consume(',') {
+			result.push(item(self)?);
+		}
+		self.expect(')')?;
+		Ok(result)
+	}
+
+	fn number(&mut self) -> Result<f64, WktError> {
+		self.skip_whitespace();
+		let start = self.pos;
+		let token = self.take_while(|c| c.is_ascii_digit() || "+-.eE".contains(c));
+		token.parse().map_err(|_| WktError {
+			position: start,
+			message: format!("can't parse number `{}`", token),
+		})
+	}
+
+	fn coordinate(&mut self) -> Result<Coordinate<f64>, WktError> {
+		let x = self.number()?;
+		let y = self.number()?;
+		// Z and M values are ignored
+		while !self.s[self.pos..]
+			.trim_start()
+			.starts_with(|c| c == ',' || c == ')')
+		{
+			self.number()?;
+		}
+		Ok(Coordinate { x, y })
+	}
+
+	fn polygon(&mut self) -> Result<Option<Polygon<f64>>, WktError> {
+		let mut rings = self
+			.list(|p| p.list(Self::coordinate).map(LineString))?
+			.into_iter();
+		Ok(rings
+			.next()
+			.map(|exterior| Polygon::new(exterior, rings.collect())))
+	}
+
+	fn geometry(&mut self, result: &mut Vec<Polygon<f64>>) -> Result<(), WktError> {
+		let start = self.pos;
+		let keyword = self.keyword();
+		// Dimension suffix like `POLYGON Z`
+		let dimension_start = self.pos;
+		if !matches!(self.keyword().as_str(), "Z" | "M" | "ZM") {
+			self.pos = dimension_start;
+		}
+		match keyword.as_str() {
+			"POLYGON" => result.extend(self.polygon()?),
+			"MULTIPOLYGON" => {
+				for polygon in self.list(Self::polygon)? {
+					result.extend(polygon);
+				}
+			},
+			"GEOMETRYCOLLECTION" => {
+				self.list(|p| p.geometry(result))?;
+			},
+			_ => {
+				self.pos = start;
+				return Err(self.error("expected POLYGON, MULTIPOLYGON or GEOMETRYCOLLECTION"));
+			},
+		}
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use geo::{LineString, MultiPolygon, Polygon};
+
+	use super::{from_wkt, to_wkt, WktError};
+
+	fn square_with_hole() -> Polygon<f64> {
+		Polygon::new(
+			LineString::from(vec![(0., 0.), (4., 0.), (4., 4.), (0., 4.), (0., 0.)]),
+			vec![LineString::from(vec![
+				(1., 1.),
+				(1., 3.),
+				(3., 3.),
+				(3., 1.),
+				(1., 1.),
+			])],
+		)
+	}
+
+	fn triangle() -> Polygon<f64> {
+		Polygon::new(
+			LineString::from(vec![(5., 0.), (6.5, 0.), (5., 1e-3), (5., 0.)]),
+			vec![],
+		)
+	}
+
+	#[test]
+	fn polygons() {
+		let polygon =
+			from_wkt("POLYGON ((0 0, 4 0, 4 4, 0 4, 0 0), (1 1, 1 3, 3 3, 3 1, 1 1))").unwrap();
+		assert_eq!(polygon, MultiPolygon(vec![square_with_hole()]));
+
+		let expected = MultiPolygon(vec![square_with_hole(), triangle()]);
+		for text in &[
+			"MULTIPOLYGON (((0 0, 4 0, 4 4, 0 4, 0 0), (1 1, 1 3, 3 3, 3 1, 1 1)), ((5 0, 6.5 0, 5 1e-3, 5 0)))",
+			// Case, whitespace, dimensions and empty parts
+			"multipolygon z(((0 0 1,4 0 1,4 4 1,0 4 1,0 0 1),(1 1 2,1 3 2,3 3 2,3 1 2,1 1 2)),EMPTY,\n((5 0 0,6.5 0 0,5 1e-3 0,5 0 0)))",
+			"GEOMETRYCOLLECTION (POLYGON ((0 0, 4 0, 4 4, 0 4, 0 0), (1 1, 1 3, 3 3, 3 1, 1 1)), GEOMETRYCOLLECTION (POLYGON ZM ((5 0 0 0, 6.5 0 0 0, 5 1e-3 0 0, 5 0 0 0))))",
+		] {
+			assert_eq!(from_wkt(text).unwrap(), expected, "{}", text);
+		}
+		assert_eq!(from_wkt(" POLYGON EMPTY ").unwrap(), MultiPolygon(vec![]));
+	}
+
+	#[test]
+	fn malformed() {
+		let error = |position: usize, message: &str| WktError {
+			position,
+			message: message.to_owned(),
+		};
+		assert_eq!(
+			from_wkt("POINT (1 2)"),
+			Err(error(
+				0,
+				"expected POLYGON, MULTIPOLYGON or GEOMETRYCOLLECTION"
+			))
+		);
+		assert_eq!(
+			from_wkt("POLYGON ((0 0, 1 x))"),
+			Err(error(17, "can't parse number ``"))
+		);
+		assert_eq!(
+			from_wkt("POLYGON ((0 0, 1 0, 1 1)"),
+			Err(error(24, "expected `)`"))
+		);
+		assert_eq!(
+			from_wkt("POLYGON (0 0, 1 0, 1 1)"),
+			Err(error(9, "expected `(`"))
+		);
+		assert_eq!(
+			from_wkt("POLYGON EMPTY POLYGON EMPTY"),
+			Err(error(14, "unexpected text after geometry"))
+		);
+		assert!(from_wkt("POLYGON ((0 0, 1 0, 1 1").is_err());
+	}
+
+	#[test]
+	fn round_trip() {
+		for polygon in &[
+			MultiPolygon(vec![]),
+			MultiPolygon(vec![square_with_hole()]),
+			MultiPolygon(vec![square_with_hole(), triangle()]),
+			MultiPolygon(vec![Polygon::new(
+				LineString::from(vec![(0.1, -1e-20), (1. / 3., 2e20), (-0.7, 0.25)]),
+				vec![],
+			)]),
+		] {
+			let text = to_wkt(polygon);
+			assert_eq!(&from_wkt(&text).unwrap(), polygon, "{}", text);
+		}
+		assert_eq!(to_wkt(&MultiPolygon(vec![])), "MULTIPOLYGON EMPTY");
+	}
+}